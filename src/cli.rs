@@ -1,9 +1,16 @@
 //! Command line interface.
 
-use crate::targets::{parse_targets, Target};
-use clap::Parser;
+use crate::{
+    env::Shell as ExportShell,
+    targets::{parse_targets, Target},
+    toolchain::gcc::DEFAULT_GCC_RELEASE,
+};
+use clap::{
+    builder::{PossibleValuesParser, TypedValueParser},
+    Parser,
+};
 use clap_complete::Shell;
-use std::{collections::HashSet, path::PathBuf};
+use std::{collections::HashSet, num::NonZeroUsize, path::PathBuf, str::FromStr};
 
 #[derive(Debug, Parser)]
 pub struct CompletionsOpts {
@@ -14,57 +21,253 @@ pub struct CompletionsOpts {
     pub shell: Shell,
 }
 
+#[derive(Debug, Parser)]
+pub struct ContainerizeOpts {
+    /// Also writes a `.devcontainer/devcontainer.json` using the generated Dockerfile.
+    #[arg(long)]
+    pub devcontainer: bool,
+    /// Verbosity level of the logs.
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    pub log_level: String,
+    /// Directory where the files are generated.
+    #[arg(short = 'p', long, default_value = ".")]
+    pub path: PathBuf,
+    /// Skips parsing Xtensa Rust version.
+    #[arg(short = 'k', long, requires = "toolchain_version")]
+    pub skip_version_parse: bool,
+    /// Only install toolchains required for STD applications in the image.
+    #[arg(short = 's', long)]
+    pub std: bool,
+    /// Comma or space separated list of targets [esp32,esp32c2,esp32c3,esp32c6,esp32h2,esp32s2,esp32s3,all].
+    #[arg(short = 't', long, default_value = "all", value_parser = parse_targets)]
+    pub targets: HashSet<Target>,
+    /// Xtensa Rust toolchain version to pin in the image.
+    ///
+    /// By default, the latest version is pinned.
+    #[arg(short = 'v', long)]
+    pub toolchain_version: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct EnvOpts {
+    /// Output format.
+    #[arg(long, default_value = "shell", value_parser = ["shell", "json"], env = "ESPUP_FORMAT")]
+    pub format: String,
+    /// Verbosity level of the logs.
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"], env = "ESPUP_LOG_LEVEL")]
+    pub log_level: String,
+    /// Xtensa Rust toolchain name.
+    #[arg(short = 'a', long, default_value = "esp", env = "ESPUP_NAME")]
+    pub name: String,
+    /// Shell to print the exports for, when using the shell format.
+    ///
+    /// By default, the shell running espup is detected, falling back to the `SHELL` environment variable.
+    #[arg(long, value_enum, env = "ESPUP_SHELL")]
+    pub shell: Option<ExportShell>,
+}
+
+#[derive(Debug, Parser)]
+pub struct InitProjectOpts {
+    /// Verbosity level of the logs.
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    pub log_level: String,
+    /// Xtensa Rust toolchain name, pinned for Xtensa targets.
+    #[arg(short = 'a', long, default_value = "esp")]
+    pub name: String,
+    /// Nightly Rust toolchain version, pinned for RISC-V targets.
+    #[arg(short = 'n', long, default_value = "nightly")]
+    pub nightly_version: String,
+    /// Path to the project.
+    #[arg(short = 'p', long, default_value = ".")]
+    pub path: PathBuf,
+    /// Configures the project to use the Rust standard library (esp-idf-sys based).
+    #[arg(short = 's', long)]
+    pub std: bool,
+    /// Writes the rust-analyzer settings to `.vscode/settings.json`.
+    ///
+    /// The variables of the installed environment (e.g. `LIBCLANG_PATH`) are added as well, so
+    /// rust-analyzer can build the project like cargo does in a terminal.
+    #[arg(long)]
+    pub vscode: bool,
+    /// Chip to configure the project for.
+    #[arg(short = 't', long, value_parser = PossibleValuesParser::new(["esp32", "esp32c2", "esp32c3", "esp32c6", "esp32h2", "esp32s2", "esp32s3"]).map(|t| Target::from_str(&t).unwrap()))]
+    pub target: Target,
+}
+
 #[derive(Debug, Parser)]
 pub struct InstallOpts {
+    /// Installs CMake and Ninja.
+    ///
+    /// These are required by the esp-idf-sys native builder, which fetches ESP-IDF by itself.
+    #[arg(long, env = "ESPUP_BUILD_TOOLS")]
+    pub build_tools: bool,
     /// Target triple of the host.
-    #[arg(short = 'd', long, value_parser = ["x86_64-unknown-linux-gnu", "aarch64-unknown-linux-gnu", "x86_64-pc-windows-msvc", "x86_64-pc-windows-gnu" , "x86_64-apple-darwin" , "aarch64-apple-darwin"])]
+    #[arg(short = 'd', long, value_parser = ["x86_64-unknown-linux-gnu", "aarch64-unknown-linux-gnu", "x86_64-pc-windows-msvc", "x86_64-pc-windows-gnu" , "x86_64-apple-darwin" , "aarch64-apple-darwin"], env = "ESPUP_DEFAULT_HOST")]
     pub default_host: Option<String>,
     /// Install Espressif RISC-V toolchain built with croostool-ng
     ///
     /// Only install this if you don't want to use the systems RISC-V toolchain
-    #[arg(short = 'r', long)]
+    #[arg(short = 'r', long, env = "ESPUP_ESP_RISCV_GCC")]
     pub esp_riscv_gcc: bool,
+    /// Relative or full path of a dotenv file (`KEY=VALUE` lines) to write the exports to, for IDEs and tools reading dotenv files.
+    ///
+    /// The `PATH` is written with its full value, as dotenv files can not reference other variables.
+    #[arg(long, env = "ESPUP_DOTENV")]
+    pub dotenv: Option<PathBuf>,
+    /// Adds the exports to the `.envrc` file of the current directory, to be loaded by direnv.
+    #[arg(long, env = "ESPUP_ENVRC")]
+    pub envrc: bool,
+    /// Appends the exports to the `GITHUB_ENV` and `GITHUB_PATH` files of GitHub Actions, making them available to the following steps.
+    ///
+    /// Enabled by default when running in GitHub Actions (`GITHUB_ACTIONS=true`).
+    #[arg(long, env = "ESPUP_GITHUB_ACTIONS")]
+    pub github_actions: bool,
     /// Relative or full path for the export file that will be generated. If no path is provided, the file will be generated under home directory (https://docs.rs/dirs/latest/dirs/fn.home_dir.html).
-    #[arg(short = 'f', long)]
+    ///
+    /// Use '-' to print the exports to stdout instead, logs are always written to stderr.
+    #[arg(short = 'f', long, env = "ESPUP_EXPORT_FILE")]
     pub export_file: Option<PathBuf>,
+    /// Template used to render the export file.
+    ///
+    /// The '{{exports}}' placeholder is replaced by the instructions setting up the environment, '{{path}}' by the directories added to the `PATH` and '{{<NAME>}}' by the value of the `<NAME>` variable (e.g. '{{LIBCLANG_PATH}}').
+    #[arg(long, env = "ESPUP_EXPORT_TEMPLATE")]
+    pub export_template: Option<PathBuf>,
     /// Extends the LLVM installation.
     ///
     /// This will install the whole LLVM instead of only installing the libs.
-    #[arg(short = 'e', long)]
+    #[arg(short = 'e', long, env = "ESPUP_EXTENDED_LLVM")]
     pub extended_llvm: bool,
+    /// Relative or full path of the directory where GCC toolchains will be installed.
+    ///
+    /// By default, GCC toolchains are installed inside the Xtensa Rust toolchain directory.
+    #[arg(long, env = "ESPUP_GCC_PATH")]
+    pub gcc_path: Option<PathBuf>,
+    /// GCC toolchain version (e.g. '13.2.0_20230928').
+    ///
+    /// Releases before '13.2.0_20230928' (e.g. '12.2.0_20230208' or 'esp-2022r1') install one
+    /// Xtensa toolchain per chip.
+    /// See https://github.com/espressif/crosstool-NG/releases for the available releases.
+    #[arg(long, default_value = DEFAULT_GCC_RELEASE, env = "ESPUP_GCC_VERSION")]
+    pub gcc_version: String,
+    /// Installs GDB for the selected targets.
+    #[arg(long, env = "ESPUP_GDB")]
+    pub gdb: bool,
+    /// Unix socket, or named pipe on Windows, of a client like an IDE.
+    ///
+    /// The progress events are sent to it as newline-delimited JSON, and it answers the confirmation prompts with `{"answer": true}` or `{"answer": false}`.
+    #[arg(long, env = "ESPUP_IPC")]
+    pub ipc: Option<PathBuf>,
+    /// Maximum number of components (e.g. GCC toolchains for each chip) downloaded and installed in parallel.
+    #[arg(short = 'j', long, default_value = "4", env = "ESPUP_JOBS")]
+    pub jobs: NonZeroUsize,
+    /// Creates symlinks for the legacy `xtensa-esp32-elf-clang` LLVM layout.
+    ///
+    /// This keeps build scripts that hard-code the paths used by older installations working.
+    #[arg(long, env = "ESPUP_LEGACY_LLVM_PATH")]
+    pub legacy_llvm_path: bool,
+    /// LLVM release channel.
+    ///
+    /// The nightly channel installs the latest esp-clang pre-release, which is meant for testing upcoming LLVM versions.
+    #[arg(long, default_value = "stable", value_parser = ["stable", "nightly"], conflicts_with = "llvm_version", env = "ESPUP_LLVM_CHANNEL")]
+    pub llvm_channel: String,
+    /// LLVM version to install (e.g. 'esp-16.0.4-20231113').
+    ///
+    /// By default, the version matching the Xtensa Rust toolchain is used. Different versions are installed side by side and the export file points to the selected one.
+    #[arg(long, env = "ESPUP_LLVM_VERSION")]
+    pub llvm_version: Option<String>,
     /// Verbosity level of the logs.
-    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"], env = "ESPUP_LOG_LEVEL")]
     pub log_level: String,
+    /// Format of the progress messages.
+    ///
+    /// With `json`, newline-delimited JSON events are printed to stdout when each phase starts and finishes, along with the overall progress and any error.
+    #[arg(long, default_value = "human", value_parser = ["human", "json"], env = "ESPUP_MESSAGE_FORMAT")]
+    pub message_format: String,
     /// Xtensa Rust toolchain name.
-    #[arg(short = 'a', long, default_value = "esp")]
+    #[arg(short = 'a', long, default_value = "esp", env = "ESPUP_NAME")]
     pub name: String,
     /// Nightly Rust toolchain version.
     ///
     /// Note that only RISC-V targets use nightly Rust channel.
-    #[arg(short = 'n', long, default_value = "nightly")]
+    #[arg(
+        short = 'n',
+        long,
+        default_value = "nightly",
+        env = "ESPUP_NIGHTLY_VERSION"
+    )]
     pub nightly_version: String,
+    /// Adds a block loading the export file to the profile of the shell (e.g. `.bashrc`), after backing it up.
+    ///
+    /// The block is removed when uninstalling.
+    #[arg(long, env = "ESPUP_PROFILE_INSTALL")]
+    pub profile_install: bool,
+    /// Installs probe-rs, cargo-flash and cargo-embed.
+    ///
+    /// On Linux, the udev rules required to access the debug probes are downloaded next to them.
+    #[arg(long, env = "ESPUP_PROBE_RS")]
+    pub probe_rs: bool,
+    /// Only prints warnings, errors and the final summary, overriding the log level.
+    #[arg(short = 'q', long, env = "ESPUP_QUIET")]
+    pub quiet: bool,
+    /// Generates the export file relative to an `ESPUP_HOME` variable set to the toolchain directory.
+    ///
+    /// The installed tree can then be moved, or mounted at a different path in containers, by only changing its value. GCC toolchains installed with `--gcc-path` outside of the toolchain directory keep their absolute paths.
+    #[arg(long, env = "ESPUP_RELOCATABLE")]
+    pub relocatable: bool,
+    /// Shell to generate the export file for.
+    ///
+    /// By default, the shell running espup is detected, falling back to the `SHELL` environment variable.
+    #[arg(long, value_enum, env = "ESPUP_SHELL")]
+    pub shell: Option<ExportShell>,
+    /// Skips the GCC toolchains installation.
+    ///
+    /// GCC is only required for linking, projects that link with `rust-lld` can skip it.
+    #[arg(long, conflicts_with_all = ["esp_riscv_gcc", "gcc_path", "gcc_version"], env = "ESPUP_SKIP_GCC")]
+    pub skip_gcc: bool,
+    /// Skips the LLVM installation.
+    ///
+    /// LLVM is only required by bindgen, projects that do not generate bindings (e.g. no_std applications without esp-idf-sys) can skip it.
+    #[arg(long, conflicts_with_all = ["extended_llvm", "legacy_llvm_path", "llvm_channel", "llvm_version"], env = "ESPUP_SKIP_LLVM")]
+    pub skip_llvm: bool,
     /// Skips parsing Xtensa Rust version.
-    #[arg(short = 'k', long, requires = "toolchain_version")]
+    #[arg(
+        short = 'k',
+        long,
+        requires = "toolchain_version",
+        env = "ESPUP_SKIP_VERSION_PARSE"
+    )]
     pub skip_version_parse: bool,
     /// Only install toolchains required for STD applications.
     ///
     /// With this option, espup will skip GCC installation (it will be handled by esp-idf-sys), hence you won't be able to build no_std applications.
-    #[arg(short = 's', long)]
+    #[arg(short = 's', long, env = "ESPUP_STD")]
     pub std: bool,
     /// Comma or space separated list of targets [esp32,esp32c2,esp32c3,esp32c6,esp32h2,esp32s2,esp32s3,all].
-    #[arg(short = 't', long, default_value = "all", value_parser = parse_targets)]
+    #[arg(short = 't', long, default_value = "all", value_parser = parse_targets, env = "ESPUP_TARGETS")]
     pub targets: HashSet<Target>,
+    /// Reports how long the download, extraction and configuration of each component took.
+    #[arg(long, env = "ESPUP_TIMINGS")]
+    pub timings: bool,
     /// Xtensa Rust toolchain version.
-    #[arg(short = 'v', long)]
+    #[arg(short = 'v', long, env = "ESPUP_TOOLCHAIN_VERSION")]
     pub toolchain_version: Option<String>,
+    /// Assumes yes to the confirmation prompts, e.g. before replacing an existing toolchain.
+    #[arg(short = 'y', long, env = "ESPUP_YES")]
+    pub yes: bool,
 }
 
 #[derive(Debug, Parser)]
 pub struct UninstallOpts {
     /// Verbosity level of the logs.
-    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"])]
+    #[arg(short = 'l', long, default_value = "info", value_parser = ["debug", "info", "warn", "error"], env = "ESPUP_LOG_LEVEL")]
     pub log_level: String,
     /// Xtensa Rust toolchain name.
-    #[arg(short = 'a', long, default_value = "esp")]
+    #[arg(short = 'a', long, default_value = "esp", env = "ESPUP_NAME")]
     pub name: String,
+    /// Only prints warnings and errors, overriding the log level.
+    #[arg(short = 'q', long, env = "ESPUP_QUIET")]
+    pub quiet: bool,
+    /// Assumes yes to the confirmation prompt.
+    #[arg(short = 'y', long, env = "ESPUP_YES")]
+    pub yes: bool,
 }
@@ -0,0 +1,162 @@
+//! Layered configuration from `espup.toml` files.
+//!
+//! Options are read from the user configuration and then from the configuration of the project,
+//! the closest `espup.toml` in the current directory or its ancestors. They are applied as
+//! `ESPUP_*` environment variables not set yet, so command line arguments and environment
+//! variables take precedence over them.
+
+use crate::error::Error;
+use directories::BaseDirs;
+use miette::Result;
+use std::{
+    collections::HashSet,
+    env,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+use toml_edit::{Document, Item, Value};
+
+/// Name of the configuration files.
+pub const CONFIG_FILE: &str = "espup.toml";
+
+/// Gets the existing configuration files, from the lowest to the highest precedence.
+pub fn get_config_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Some(base_dirs) = BaseDirs::new() {
+        files.push(base_dirs.config_dir().join("espup").join(CONFIG_FILE));
+    }
+    if let Ok(current_dir) = env::current_dir() {
+        if let Some(project) = current_dir
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE))
+            .find(|file| file.is_file())
+        {
+            files.push(project);
+        }
+    }
+    files.into_iter().filter(|file| file.is_file()).collect()
+}
+
+/// Gets the environment variable of an option, e.g. `ESPUP_TOOLCHAIN_VERSION` for `toolchain-version`.
+fn get_env_var(key: &str) -> String {
+    format!("ESPUP_{}", key.replace('-', "_").to_uppercase())
+}
+
+/// Converts the value of an option to the format used in the command line.
+fn format_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.value().to_string()),
+        Value::Integer(value) => Some(value.value().to_string()),
+        Value::Boolean(value) => Some(value.value().to_string()),
+        Value::Array(values) => values
+            .iter()
+            .map(format_value)
+            .collect::<Option<Vec<String>>>()
+            .map(|values| values.join(",")),
+        _ => None,
+    }
+}
+
+/// Parses the options of a configuration file into environment variables.
+pub fn parse_config(
+    contents: &str,
+    file: &Path,
+    known_vars: &HashSet<String>,
+) -> Result<Vec<(String, String)>, Error> {
+    let invalid = |reason: String| Error::InvalidConfig(file.display().to_string(), reason);
+    let doc = contents
+        .parse::<Document>()
+        .map_err(|e| invalid(e.to_string()))?;
+    let mut vars = Vec::new();
+    for (key, item) in doc.iter() {
+        let var = get_env_var(key);
+        if !known_vars.contains(&var) {
+            return Err(invalid(format!("unknown option '{key}'")));
+        }
+        let value = match item {
+            Item::Value(value) => format_value(value),
+            _ => None,
+        }
+        .ok_or_else(|| invalid(format!("unsupported value for '{key}'")))?;
+        vars.push((var, value));
+    }
+    Ok(vars)
+}
+
+/// Loads the configuration files, later files overriding the options of the previous ones.
+pub fn load_config(
+    files: &[PathBuf],
+    known_vars: &HashSet<String>,
+) -> Result<Vec<(String, String)>, Error> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    for file in files {
+        for (var, value) in parse_config(&read_to_string(file)?, file, known_vars)? {
+            vars.retain(|(v, _)| *v != var);
+            vars.push((var, value));
+        }
+    }
+    Ok(vars)
+}
+
+/// Sets the environment variables of the configuration that are not set yet.
+pub fn apply_config(vars: Vec<(String, String)>) {
+    for (var, value) in vars {
+        if env::var_os(&var).is_none() {
+            env::set_var(var, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{load_config, parse_config};
+    use std::{collections::HashSet, fs::write, path::Path};
+    use tempfile::TempDir;
+
+    fn known_vars() -> HashSet<String> {
+        [
+            "ESPUP_STD",
+            "ESPUP_TARGETS",
+            "ESPUP_TOOLCHAIN_VERSION",
+            "ESPUP_JOBS",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = "targets = [\"esp32\", \"esp32c3\"]\nstd = true\njobs = 2\ntoolchain-version = \"1.77.0.0\"\n";
+        assert_eq!(
+            parse_config(config, Path::new(crate::config::CONFIG_FILE), &known_vars()).unwrap(),
+            vec![
+                ("ESPUP_TARGETS".to_string(), "esp32,esp32c3".to_string()),
+                ("ESPUP_STD".to_string(), "true".to_string()),
+                ("ESPUP_JOBS".to_string(), "2".to_string()),
+                (
+                    "ESPUP_TOOLCHAIN_VERSION".to_string(),
+                    "1.77.0.0".to_string()
+                ),
+            ]
+        );
+        assert!(parse_config("foo = 1", Path::new("espup.toml"), &known_vars()).is_err());
+        assert!(parse_config("[std]", Path::new("espup.toml"), &known_vars()).is_err());
+    }
+
+    #[test]
+    fn test_load_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let user = temp_dir.path().join("user.toml");
+        let project = temp_dir.path().join("project.toml");
+        write(&user, "std = true\ntargets = \"esp32\"\n").unwrap();
+        write(&project, "targets = \"esp32c3\"\n").unwrap();
+        assert_eq!(
+            load_config(&[user, project], &known_vars()).unwrap(),
+            vec![
+                ("ESPUP_STD".to_string(), "true".to_string()),
+                ("ESPUP_TARGETS".to_string(), "esp32c3".to_string()),
+            ]
+        );
+    }
+}
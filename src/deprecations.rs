@@ -0,0 +1,112 @@
+//! Deprecated options, which keep working for a release while printing a migration hint.
+
+use clap::{parser::ValueSource, ArgMatches, Command};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Deprecated option.
+#[derive(Debug)]
+pub struct Deprecation {
+    /// Id of the argument, e.g. `llvm_version` for `--llvm-version`.
+    pub arg: &'static str,
+    /// espup version deprecating the option.
+    pub since: &'static str,
+    /// How to migrate away from the option.
+    pub hint: &'static str,
+}
+
+/// Options currently deprecated.
+pub const DEPRECATIONS: &[Deprecation] = &[];
+
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+impl Deprecation {
+    /// Formats the warning of the deprecated option.
+    pub fn message(&self, option: &str) -> String {
+        format!(
+            "'{}' is deprecated since espup {} and will be removed in a future release. {}",
+            option, self.since, self.hint
+        )
+    }
+}
+
+/// Finds the deprecated options set on the command line, through environment variables or the configuration files.
+pub fn find_deprecated(
+    command: &Command,
+    matches: &ArgMatches,
+    deprecations: &[Deprecation],
+) -> Vec<String> {
+    let mut commands = vec![(command, matches)];
+    if let Some((name, sub_matches)) = matches.subcommand() {
+        if let Some(subcommand) = command.find_subcommand(name) {
+            commands.push((subcommand, sub_matches));
+        }
+    }
+    let mut messages = Vec::new();
+    for (command, matches) in commands {
+        for deprecation in deprecations {
+            let Some(arg) = command
+                .get_arguments()
+                .find(|arg| arg.get_id() == deprecation.arg)
+            else {
+                continue;
+            };
+            if matches!(
+                matches.value_source(deprecation.arg),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            ) {
+                let option = arg
+                    .get_long()
+                    .map_or(deprecation.arg.to_string(), |long| format!("--{long}"));
+                messages.push(deprecation.message(&option));
+            }
+        }
+    }
+    messages
+}
+
+/// Records the warnings of the deprecated options, printed once the logger is initialized.
+pub fn record_deprecated(command: &Command, matches: &ArgMatches) {
+    WARNINGS
+        .lock()
+        .unwrap()
+        .extend(find_deprecated(command, matches, DEPRECATIONS));
+}
+
+/// Prints the recorded warnings of the deprecated options.
+pub fn warn_deprecated() {
+    for message in WARNINGS.lock().unwrap().drain(..) {
+        warn!("{message}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::deprecations::{find_deprecated, Deprecation};
+    use clap::{Arg, ArgAction, Command};
+
+    #[test]
+    fn test_find_deprecated() {
+        let command = Command::new("espup").subcommand(
+            Command::new("install")
+                .arg(Arg::new("old").long("old").action(ArgAction::SetTrue))
+                .arg(Arg::new("new").long("new").action(ArgAction::SetTrue)),
+        );
+        let deprecations = [Deprecation {
+            arg: "old",
+            since: "0.11.0",
+            hint: "Use '--new' instead.",
+        }];
+        let matches = command
+            .clone()
+            .get_matches_from(["espup", "install", "--old"]);
+        assert_eq!(
+            find_deprecated(&command, &matches, &deprecations),
+            vec!["'--old' is deprecated since espup 0.11.0 and will be removed in a future release. Use '--new' instead."]
+        );
+        let matches = command
+            .clone()
+            .get_matches_from(["espup", "install", "--new"]);
+        assert!(find_deprecated(&command, &matches, &deprecations).is_empty());
+    }
+}
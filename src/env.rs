@@ -1,24 +1,371 @@
 //! Environment variables set up and export file support.
 
 use crate::error::Error;
+#[cfg(windows)]
+use crate::toolchain::gcc::remove_path_entry;
+use clap::ValueEnum;
 use directories::BaseDirs;
-use log::debug;
+use serde::{Deserialize, Serialize};
 use std::{
     env,
-    fs::File,
-    io::Write,
+    fs::{copy, create_dir_all, read_to_string, remove_file, write, OpenOptions},
+    io::{stdout, Write},
     path::{Path, PathBuf},
 };
+use tracing::{debug, info, warn};
 #[cfg(windows)]
 use winreg::{
     enums::{HKEY_CURRENT_USER, KEY_READ, KEY_WRITE},
     RegKey,
 };
 
+/// Markers delimiting the block managed by espup in files shared with the user.
+const BLOCK_START: &str = ">>> espup >>>";
+const BLOCK_END: &str = "<<< espup <<<";
+/// Export file name that prints the exports to stdout instead of writing them to a file.
+const STDOUT_EXPORT_FILE: &str = "-";
+/// Variable holding the root of relocatable exports.
+const ROOT_VAR: &str = "ESPUP_HOME";
+/// Reference to the root variable in relocated values, expanded when quoting them.
+const ROOT_REFERENCE: &str = "${ESPUP_HOME}";
 #[cfg(windows)]
-const DEFAULT_EXPORT_FILE: &str = "export-esp.ps1";
+const PATH_SEPARATOR: char = ';';
 #[cfg(not(windows))]
-const DEFAULT_EXPORT_FILE: &str = "export-esp.sh";
+const PATH_SEPARATOR: char = ':';
+
+/// Shell the export file is generated for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    /// POSIX shells, such as sh, bash or zsh.
+    Sh,
+    /// C shells, such as csh or tcsh.
+    Csh,
+    /// Elvish.
+    Elvish,
+    /// fish.
+    Fish,
+    /// Nushell.
+    Nu,
+    /// PowerShell.
+    #[value(name = "powershell")]
+    PowerShell,
+    /// Windows Command Prompt (cmd.exe).
+    Cmd,
+}
+
+impl Shell {
+    /// Detects the shell of the user, falling back to the default one of the platform.
+    ///
+    /// The shell running espup is preferred over the login shell of the `SHELL` variable, as
+    /// users may run a different one (e.g. fish started from a bash login shell).
+    pub fn detect() -> Self {
+        match get_shell_name().as_deref().and_then(Self::from_name) {
+            Some(shell) => shell,
+            None if cfg!(windows) => Shell::PowerShell,
+            None => Shell::Sh,
+        }
+    }
+
+    /// Gets the shell from the name of its executable, e.g. `zsh`, `-bash` or `pwsh.exe`.
+    fn from_name(name: &str) -> Option<Self> {
+        let name = Path::new(name.trim_start_matches('-'))
+            .file_stem()?
+            .to_str()?
+            .to_lowercase();
+        match name.as_str() {
+            "ash" | "bash" | "dash" | "ksh" | "mksh" | "sh" | "zsh" => Some(Shell::Sh),
+            "csh" | "tcsh" => Some(Shell::Csh),
+            "elvish" => Some(Shell::Elvish),
+            "fish" => Some(Shell::Fish),
+            "nu" => Some(Shell::Nu),
+            "powershell" | "pwsh" => Some(Shell::PowerShell),
+            "cmd" => Some(Shell::Cmd),
+            _ => None,
+        }
+    }
+
+    /// Gets the default name of the export file.
+    pub fn export_file_name(&self) -> &str {
+        match self {
+            Shell::Sh => "export-esp.sh",
+            Shell::Csh => "export-esp.csh",
+            Shell::Elvish => "export-esp.elv",
+            Shell::Fish => "export-esp.fish",
+            Shell::Nu => "export-esp.nu",
+            Shell::PowerShell => "export-esp.ps1",
+            Shell::Cmd => "export-esp.bat",
+        }
+    }
+
+    /// Gets the instruction that loads the export file.
+    pub fn source_command(&self, export_file: &Path) -> String {
+        match self {
+            Shell::Sh | Shell::PowerShell => format!(". {}", export_file.display()),
+            Shell::Csh | Shell::Fish | Shell::Nu => format!("source {}", export_file.display()),
+            Shell::Elvish => format!("eval (slurp < {})", export_file.display()),
+            Shell::Cmd => format!("call {}", export_file.display()),
+        }
+    }
+
+    /// Gets the instruction that loads the export file only if it exists.
+    fn source_guarded(&self, export_file: &Path) -> String {
+        let file = export_file.display();
+        match self {
+            Shell::Sh => format!("if [ -f \"{file}\" ]; then . \"{file}\"; fi"),
+            Shell::Csh => format!("if ( -f \"{file}\" ) source \"{file}\""),
+            Shell::Elvish => format!("try {{ eval (slurp < \"{file}\") }} catch {{ }}"),
+            Shell::Fish => format!("test -f \"{file}\"; and source \"{file}\""),
+            // Nushell resolves `source` when parsing, so it can not be guarded
+            Shell::Nu => format!("source \"{file}\""),
+            Shell::PowerShell => format!("if (Test-Path \"{file}\") {{ . \"{file}\" }}"),
+            Shell::Cmd => format!("if exist \"{file}\" call \"{file}\""),
+        }
+    }
+
+    /// Gets the prefix of the comments.
+    fn comment(&self) -> &str {
+        match self {
+            // Lines of batch files start with `@` to not echo them
+            Shell::Cmd => "@rem",
+            _ => "#",
+        }
+    }
+
+    /// Gets the profile loaded by the shell on startup, if the shell has one.
+    pub fn get_profile(&self) -> Option<PathBuf> {
+        let base_dirs = BaseDirs::new().unwrap();
+        let home_dir = base_dirs.home_dir();
+        let profile = match self {
+            Shell::Sh => {
+                let shell = get_shell_name().unwrap_or_default();
+                match Path::new(shell.trim_start_matches('-'))
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                {
+                    Some("bash") => home_dir.join(".bashrc"),
+                    Some("zsh") => env::var_os("ZDOTDIR")
+                        .map_or(home_dir.to_path_buf(), PathBuf::from)
+                        .join(".zshrc"),
+                    _ => home_dir.join(".profile"),
+                }
+            }
+            Shell::Csh => {
+                let shell = get_shell_name().unwrap_or_default();
+                match Path::new(shell.trim_start_matches('-'))
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                {
+                    Some("tcsh") => home_dir.join(".tcshrc"),
+                    _ => home_dir.join(".cshrc"),
+                }
+            }
+            Shell::Elvish => home_dir.join(".config").join("elvish").join("rc.elv"),
+            Shell::Fish => home_dir.join(".config").join("fish").join("config.fish"),
+            Shell::Nu => base_dirs.config_dir().join("nushell").join("config.nu"),
+            #[cfg(windows)]
+            Shell::PowerShell => home_dir
+                .join("Documents")
+                .join("PowerShell")
+                .join("Microsoft.PowerShell_profile.ps1"),
+            #[cfg(not(windows))]
+            Shell::PowerShell => home_dir
+                .join(".config")
+                .join("powershell")
+                .join("Microsoft.PowerShell_profile.ps1"),
+            // cmd.exe only runs the `AutoRun` command of the registry on startup
+            Shell::Cmd => return None,
+        };
+        Some(profile)
+    }
+
+    /// Quotes a value, expanding the references to the root variable of relocatable exports.
+    fn quote(&self, value: &str) -> String {
+        if !value.contains(ROOT_REFERENCE) {
+            return format!("\"{value}\"");
+        }
+        match self {
+            Shell::Sh => format!("\"{value}\""),
+            Shell::Csh => format!(
+                "\"{}\"",
+                value.replace(ROOT_REFERENCE, &format!("${{{ROOT_VAR}}}"))
+            ),
+            Shell::Fish => format!(
+                "\"{}\"",
+                value.replace(ROOT_REFERENCE, &format!("${ROOT_VAR}"))
+            ),
+            Shell::PowerShell => format!(
+                "\"{}\"",
+                value.replace(ROOT_REFERENCE, &format!("${{Env:{ROOT_VAR}}}"))
+            ),
+            Shell::Cmd => format!(
+                "\"{}\"",
+                value.replace(ROOT_REFERENCE, &format!("%{ROOT_VAR}%"))
+            ),
+            // Elvish and Nushell do not expand variables in double quoted strings
+            Shell::Elvish => value
+                .split(ROOT_REFERENCE)
+                .map(|part| {
+                    if part.is_empty() {
+                        String::new()
+                    } else {
+                        format!("\"{part}\"")
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(&format!("$E:{ROOT_VAR}")),
+            Shell::Nu => format!(
+                "$\"{}\"",
+                value
+                    .replace('(', "\\(")
+                    .replace(ROOT_REFERENCE, &format!("($env.{ROOT_VAR})"))
+            ),
+        }
+    }
+
+    /// Formats the instruction that sets an environment variable.
+    fn format_var(&self, name: &str, value: &str) -> String {
+        let value = self.quote(value);
+        match self {
+            Shell::Sh => format!("export {name}={value}"),
+            Shell::Csh => format!("setenv {name} {value}"),
+            Shell::Elvish => format!("set E:{name} = {value}"),
+            Shell::Fish => format!("set -gx {name} {value}"),
+            Shell::Nu => format!("$env.{name} = {value}"),
+            Shell::PowerShell => format!("$Env:{name} = {value}"),
+            // The quotes of `set` wrap the whole assignment
+            Shell::Cmd => format!("@set \"{name}={}", &value[1..]),
+        }
+    }
+
+    /// Formats the instruction that prepends the directories to the `PATH`, unless it already
+    /// contains them.
+    fn format_path(&self, paths: &[String]) -> String {
+        let sep = PATH_SEPARATOR;
+        match self {
+            Shell::Sh => {
+                let paths = self.quote(&paths.join(&sep.to_string()));
+                let paths = &paths[1..paths.len() - 1];
+                format!("case \"{sep}$PATH{sep}\" in *\"{sep}{paths}{sep}\"*) ;; *) export PATH=\"{paths}{sep}$PATH\" ;; esac")
+            }
+            Shell::Csh => {
+                let paths = self.quote(&paths.join(&sep.to_string()));
+                let paths = &paths[1..paths.len() - 1];
+                format!("if ( \"{sep}${{PATH}}{sep}\" !~ \"*{sep}{paths}{sep}*\" ) setenv PATH \"{paths}{sep}${{PATH}}\"")
+            }
+            Shell::Elvish => {
+                let paths: Vec<String> = paths.iter().map(|p| self.quote(p)).collect();
+                format!(
+                    "set paths = [(each {{|p| if (not (has-value $paths $p)) {{ put $p }} }} [{}]) $@paths]",
+                    paths.join(" ")
+                )
+            }
+            Shell::Fish => {
+                let paths: Vec<String> = paths.iter().map(|p| self.quote(p)).collect();
+                // fish_add_path skips the directories already in the PATH
+                format!("fish_add_path --path --prepend {}", paths.join(" "))
+            }
+            Shell::Nu => {
+                let paths: Vec<String> = paths.iter().map(|p| self.quote(p)).collect();
+                // The PATH may be either a string or a list, uniq drops the directories already in it
+                format!(
+                    "$env.PATH = ($env.PATH | split row (char esep) | prepend [{}] | uniq)",
+                    paths.join(" ")
+                )
+            }
+            Shell::PowerShell => {
+                let paths = self.quote(&paths.join(&sep.to_string()));
+                let paths = &paths[1..paths.len() - 1];
+                format!("if (-not \"$Env:PATH{sep}\".Contains(\"{paths}{sep}\")) {{ $Env:PATH = \"{paths}{sep}\" + $Env:PATH }}")
+            }
+            Shell::Cmd => {
+                let paths = self.quote(&paths.join(&sep.to_string()));
+                let paths = &paths[1..paths.len() - 1];
+                format!("@echo \"{sep}%PATH%{sep}\" | find /i \"{sep}{paths}{sep}\" >nul || set \"PATH={paths}{sep}%PATH%\"")
+            }
+        }
+    }
+}
+
+/// Environment change required by an installed tool.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub enum Export {
+    /// Directory prepended to the `PATH`.
+    Path(String),
+    /// Environment variable and its value.
+    Var(String, String),
+}
+
+/// Gets the name of the shell of the user: the parent process if it is a known shell, otherwise
+/// the login shell of the `SHELL` variable.
+fn get_shell_name() -> Option<String> {
+    get_parent_process_name()
+        .filter(|name| Shell::from_name(name).is_some())
+        .or_else(|| env::var("SHELL").ok())
+}
+
+/// Gets the name of the parent process.
+#[cfg(unix)]
+fn get_parent_process_name() -> Option<String> {
+    let ppid = std::os::unix::process::parent_id();
+    if let Ok(comm) = read_to_string(format!("/proc/{ppid}/comm")) {
+        return Some(comm.trim().to_string());
+    }
+    // No procfs, e.g. on macOS
+    let output = std::process::Command::new("ps")
+        .args(["-o", "comm=", "-p", &ppid.to_string()])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Gets the name of the parent process.
+#[cfg(windows)]
+fn get_parent_process_name() -> Option<String> {
+    use std::mem::{size_of, zeroed};
+    use winapi::um::{
+        handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+        processthreadsapi::GetCurrentProcessId,
+        tlhelp32::{
+            CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+            TH32CS_SNAPPROCESS,
+        },
+    };
+
+    // (process id, parent process id, executable name) of the running processes
+    let mut processes = Vec::new();
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return None;
+        }
+        let mut entry: PROCESSENTRY32W = zeroed();
+        entry.dwSize = size_of::<PROCESSENTRY32W>() as u32;
+        let mut found = Process32FirstW(snapshot, &mut entry) != 0;
+        while found {
+            let exe_file = &entry.szExeFile;
+            let len = exe_file
+                .iter()
+                .position(|&c| c == 0)
+                .unwrap_or(exe_file.len());
+            processes.push((
+                entry.th32ProcessID,
+                entry.th32ParentProcessID,
+                String::from_utf16_lossy(&exe_file[..len]),
+            ));
+            found = Process32NextW(snapshot, &mut entry) != 0;
+        }
+        CloseHandle(snapshot);
+    }
+    let pid = unsafe { GetCurrentProcessId() };
+    let (_, ppid, _) = processes.iter().find(|(id, _, _)| *id == pid)?;
+    processes
+        .iter()
+        .find(|(id, _, _)| id == ppid)
+        .map(|(_, _, name)| name.clone())
+}
 
 #[cfg(windows)]
 /// Sets an environment variable for the current user.
@@ -71,9 +418,12 @@ pub fn delete_env_variable(key: &str) -> Result<(), Error> {
     Ok(())
 }
 
-/// Returns the absolute path to the export file, uses the DEFAULT_EXPORT_FILE if no arg is provided.
-pub fn get_export_file(export_file: Option<PathBuf>) -> Result<PathBuf, Error> {
+/// Returns the absolute path to the export file, uses the default one of the shell if no arg is provided.
+pub fn get_export_file(export_file: Option<PathBuf>, shell: Shell) -> Result<PathBuf, Error> {
     if let Some(export_file) = export_file {
+        if is_stdout(&export_file) {
+            return Ok(export_file);
+        }
         if export_file.is_dir() {
             return Err(Error::InvalidDestination(export_file.display().to_string()));
         }
@@ -87,70 +437,457 @@ pub fn get_export_file(export_file: Option<PathBuf>) -> Result<PathBuf, Error> {
         Ok(BaseDirs::new()
             .unwrap()
             .home_dir()
-            .join(DEFAULT_EXPORT_FILE))
+            .join(shell.export_file_name()))
     }
 }
 
+/// Returns true if the exports are printed to stdout instead of written to a file.
+pub fn is_stdout(export_file: &Path) -> bool {
+    export_file == Path::new(STDOUT_EXPORT_FILE)
+}
+
+/// Normalizes a path, using the platform separators and removing trailing ones.
+fn normalize_path(path: &str) -> String {
+    #[cfg(windows)]
+    let path = path.replace('/', r"\");
+    path.trim_end_matches(['/', '\\']).to_string()
+}
+
+/// Splits the exports into variables, deduplicated by name with the last value winning, and
+/// deduplicated `PATH` directories.
+pub(crate) fn normalize_exports(exports: &[Export]) -> (Vec<(&str, String)>, Vec<String>) {
+    let mut vars: Vec<(&str, String)> = Vec::new();
+    let mut paths: Vec<String> = Vec::new();
+    for export in exports {
+        match export {
+            Export::Path(path) => {
+                let path = normalize_path(path);
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+            Export::Var(name, value) => {
+                vars.retain(|(n, _)| n != name);
+                vars.push((name, normalize_path(value)));
+            }
+        }
+    }
+    vars.sort();
+    (vars, paths)
+}
+
+/// Tools that break builds when another installation shadows them in the `PATH`.
+const SHADOWING_TOOLS: &[&str] = &[
+    "clang",
+    "riscv32-esp-elf-gcc",
+    "riscv32-esp-elf-gdb",
+    "xtensa-esp-elf-gcc",
+    "xtensa-esp-elf-gdb",
+    "xtensa-esp32-elf-gcc",
+    "xtensa-esp32s2-elf-gcc",
+    "xtensa-esp32s3-elf-gcc",
+];
+
+/// Finds the tools of the exports shadowed by other installations earlier in the `PATH`.
+///
+/// Returns the shadowed tool and the path of the conflicting executable. Exported directories
+/// missing from the `PATH` are prepended when loading the exports, so only those already in the
+/// `PATH` can be shadowed.
+pub fn find_shadowed_tools(exports: &[Export], path: &str) -> Vec<(String, PathBuf)> {
+    let (_, exported) = normalize_exports(exports);
+    let entries: Vec<PathBuf> = env::split_paths(path).collect();
+    let mut shadowed = Vec::new();
+    for tool in SHADOWING_TOOLS {
+        let file_name = format!("{tool}{}", env::consts::EXE_SUFFIX);
+        let Some(installed) = exported
+            .iter()
+            .map(PathBuf::from)
+            .find(|dir| dir.join(&file_name).is_file())
+        else {
+            continue;
+        };
+        let Some(position) = entries.iter().position(|entry| {
+            normalize_path(&entry.to_string_lossy()) == installed.to_string_lossy()
+        }) else {
+            continue;
+        };
+        if let Some(conflicting) = entries[..position]
+            .iter()
+            .map(|entry| entry.join(&file_name))
+            .find(|file| file.is_file())
+        {
+            shadowed.push((tool.to_string(), conflicting));
+        }
+    }
+    shadowed
+}
+
+/// Warns about the tools of the exports shadowed by other installations in the `PATH`.
+pub fn warn_shadowed_tools(exports: &[Export]) {
+    let path = env::var("PATH").unwrap_or_default();
+    for (tool, conflicting) in find_shadowed_tools(exports, &path) {
+        warn!(
+            "'{}' is earlier in the PATH and shadows the installed '{}', remove it from the PATH or move its entry after the espup ones",
+            conflicting.display(),
+            tool
+        );
+    }
+}
+
+/// Formats the exports as shell instructions.
+///
+/// All the directories are prepended to the `PATH` in a single instruction that is skipped if
+/// the `PATH` already contains them, so sourcing the file repeatedly does not grow the `PATH`.
+pub fn format_exports(exports: &[Export], shell: Shell) -> Vec<String> {
+    let (vars, paths) = normalize_exports(exports);
+    let mut lines: Vec<String> = Vec::new();
+    for (name, value) in vars {
+        lines.push(shell.format_var(name, &value));
+    }
+    if !paths.is_empty() {
+        lines.push(shell.format_path(&paths));
+    }
+    lines
+}
+
+/// Formats the exports as shell instructions relative to the `ESPUP_HOME` variable, so the
+/// installed tree can be moved by only changing its value.
+///
+/// Values outside of the root are kept as they are.
+pub fn format_relocatable_exports(exports: &[Export], shell: Shell, root: &Path) -> Vec<String> {
+    let root = normalize_path(&root.display().to_string());
+    let relocate = |value: &str| {
+        let value = normalize_path(value);
+        match value.strip_prefix(&root) {
+            Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+                format!("{ROOT_REFERENCE}{rest}")
+            }
+            _ => value,
+        }
+    };
+    let exports: Vec<Export> = exports
+        .iter()
+        .map(|export| match export {
+            Export::Path(path) => Export::Path(relocate(path)),
+            Export::Var(name, value) => Export::Var(name.clone(), relocate(value)),
+        })
+        .collect();
+    let mut lines = vec![shell.format_var(ROOT_VAR, &root)];
+    lines.extend(format_exports(&exports, shell));
+    lines
+}
+
+/// Renders an export file template.
+///
+/// The template may use the following placeholders:
+/// - `{{exports}}`: the instructions setting up the environment.
+/// - `{{path}}`: the directories added to the `PATH`, joined with the platform separator.
+/// - `{{<NAME>}}`: the value of the `<NAME>` variable, e.g. `{{LIBCLANG_PATH}}`.
+pub fn render_template(
+    template: &str,
+    lines: &[String],
+    exports: &[Export],
+) -> Result<Vec<String>, Error> {
+    let (vars, paths) = normalize_exports(exports);
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..].find("}}").ok_or_else(|| {
+            Error::InvalidTemplate(rest[start..].lines().next().unwrap().to_string())
+        })?;
+        let placeholder = rest[start + 2..start + end].trim();
+        rendered.push_str(&rest[..start]);
+        match placeholder {
+            "exports" => rendered.push_str(&lines.join("\n")),
+            "path" => rendered.push_str(&paths.join(&PATH_SEPARATOR.to_string())),
+            name => match vars.iter().find(|(n, _)| *n == name) {
+                Some((_, value)) => rendered.push_str(value),
+                None => return Err(Error::InvalidTemplate(format!("{{{{{placeholder}}}}}"))),
+            },
+        }
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered.lines().map(str::to_string).collect())
+}
+
 /// Creates the export file with the necessary environment variables.
-pub fn create_export_file(export_file: &PathBuf, exports: &[String]) -> Result<(), Error> {
-    debug!("Creating export file");
-    let mut file = File::create(export_file)?;
-    for e in exports.iter() {
-        #[cfg(windows)]
-        let e = e.replace('/', r"\");
-        file.write_all(e.as_bytes())?;
-        file.write_all(b"\n")?;
+///
+/// The exports are written to a block delimited by markers, if the file already exists only
+/// that block is updated, preserving any user additions. If the export file is `-`, the exports
+/// are printed to stdout instead.
+pub fn create_export_file(
+    export_file: &PathBuf,
+    lines: &[String],
+    shell: Shell,
+) -> Result<(), Error> {
+    if is_stdout(export_file) {
+        debug!("Printing exports to stdout");
+        let mut stdout = stdout();
+        for e in lines {
+            stdout.write_all(e.as_bytes())?;
+            stdout.write_all(b"\n")?;
+        }
+        return Ok(());
     }
 
+    let contents = if export_file.exists() {
+        let contents = read_to_string(export_file)?;
+        if !contents.contains(BLOCK_START) {
+            warn!(
+                "Export file '{}' has no espup block, appending it. Remove any outdated exports above it",
+                export_file.display()
+            );
+        }
+        contents
+    } else {
+        String::new()
+    };
+    debug!("Writing export file");
+    write(
+        export_file,
+        update_managed_block(&contents, lines, shell.comment()),
+    )?;
+
     Ok(())
 }
 
-#[cfg(windows)]
-/// Instructions to export the environment variables.
-pub fn set_env() -> Result<(), Error> {
-    let mut path = env::var("PATH").unwrap_or_default();
+/// Formats the exports as a JSON document, with the variables and the directories added to the `PATH`.
+pub fn format_json(exports: &[Export]) -> String {
+    let (vars, paths) = normalize_exports(exports);
+    let variables: serde_json::Map<String, serde_json::Value> = vars
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.into()))
+        .collect();
+    serde_json::to_string_pretty(&serde_json::json!({
+        "variables": variables,
+        "path": paths,
+    }))
+    .unwrap()
+}
 
-    if let Ok(xtensa_gcc) = env::var("XTENSA_GCC") {
-        let xtensa_gcc: &str = &xtensa_gcc;
-        if !path.contains(xtensa_gcc) {
-            path = format!("{};{}", xtensa_gcc, path);
+/// Formats the exports as `KEY=VALUE` lines, without any shell syntax.
+///
+/// As dotenv files can not reference other variables, `PATH` is set to its full value: the
+/// directories followed by the given `PATH`.
+pub fn format_dotenv(exports: &[Export], path: &str) -> Vec<String> {
+    let (vars, paths) = normalize_exports(exports);
+    let mut lines: Vec<String> = vars
+        .into_iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect();
+    if !paths.is_empty() {
+        let mut entries = paths;
+        for entry in env::split_paths(path) {
+            let entry = entry.display().to_string();
+            if !entry.is_empty() && !entries.contains(&entry) {
+                entries.push(entry);
+            }
         }
+        lines.push(format!(
+            "PATH={}",
+            entries.join(&PATH_SEPARATOR.to_string())
+        ));
     }
+    lines
+}
 
-    if let Ok(riscv_gcc) = env::var("RISCV_GCC") {
-        let riscv_gcc: &str = &riscv_gcc;
-        if !path.contains(riscv_gcc) {
-            path = format!("{};{}", riscv_gcc, path);
-        }
+/// Creates a dotenv file with the necessary environment variables.
+pub fn create_dotenv_file(dotenv_file: &Path, exports: &[Export]) -> Result<(), Error> {
+    debug!("Creating dotenv file '{}'", dotenv_file.display());
+    let path = env::var("PATH").unwrap_or_default();
+    let mut contents = format_dotenv(exports, &path).join("\n");
+    contents.push('\n');
+    write(dotenv_file, contents)?;
+    Ok(())
+}
+
+/// Returns true if running in GitHub Actions.
+pub fn is_github_actions() -> bool {
+    env::var("GITHUB_ACTIONS").as_deref() == Ok("true")
+}
+
+/// Appends the exports to the `GITHUB_ENV` and `GITHUB_PATH` files, so they are available in the
+/// following steps of a GitHub Actions job.
+pub fn update_github_env(exports: &[Export]) -> Result<(), Error> {
+    let (Some(github_env), Some(github_path)) =
+        (env::var_os("GITHUB_ENV"), env::var_os("GITHUB_PATH"))
+    else {
+        warn!("'GITHUB_ENV' or 'GITHUB_PATH' are not set, skipping GitHub Actions environment");
+        return Ok(());
+    };
+    write_github_env(exports, Path::new(&github_env), Path::new(&github_path))
+}
+
+/// Appends the variables of the exports to the `github_env` file and their directories to the
+/// `github_path` file.
+fn write_github_env(
+    exports: &[Export],
+    github_env: &Path,
+    github_path: &Path,
+) -> Result<(), Error> {
+    let (vars, paths) = normalize_exports(exports);
+    debug!("Appending variables to '{}'", github_env.display());
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(github_env)?;
+    for (name, value) in vars {
+        writeln!(file, "{name}={value}")?;
+    }
+    debug!("Appending directories to '{}'", github_path.display());
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(github_path)?;
+    for path in paths {
+        writeln!(file, "{path}")?;
+    }
+    info!("Environment exported to the following GitHub Actions steps");
+    Ok(())
+}
+
+/// Finds the block managed by espup in the contents, including the whole lines of its markers.
+fn find_managed_block(contents: &str) -> Option<(usize, usize)> {
+    let start = contents.find(BLOCK_START)?;
+    let end = start + contents[start..].find(BLOCK_END)?;
+    let start = contents[..start].rfind('\n').map_or(0, |i| i + 1);
+    let end = contents[end..]
+        .find('\n')
+        .map_or(contents.len(), |i| end + i + 1);
+    Some((start, end))
+}
+
+/// Replaces the block managed by espup in the contents, appending it if there is none.
+///
+/// The markers of the block are commented out with the given comment prefix.
+fn update_managed_block(contents: &str, lines: &[String], comment: &str) -> String {
+    let block = format!(
+        "{comment} {BLOCK_START}\n{}\n{comment} {BLOCK_END}\n",
+        lines.join("\n")
+    );
+    match find_managed_block(contents) {
+        Some((start, end)) => format!("{}{}{}", &contents[..start], block, &contents[end..]),
+        _ if contents.is_empty() || contents.ends_with('\n') => format!("{contents}{block}"),
+        _ => format!("{contents}\n{block}"),
     }
+}
+
+/// Adds the exports to a direnv `.envrc` file, keeping any other content of the file.
+pub fn update_envrc(envrc: &Path, exports: &[Export]) -> Result<(), Error> {
+    debug!("Updating '{}'", envrc.display());
+    let contents = if envrc.exists() {
+        read_to_string(envrc)?
+    } else {
+        String::new()
+    };
+    write(
+        envrc,
+        update_managed_block(&contents, &format_exports(exports, Shell::Sh), "#"),
+    )?;
+    info!(
+        "Updated '{}', run 'direnv allow' to load it",
+        envrc.display()
+    );
+    Ok(())
+}
 
-    if let Ok(libclang_path) = env::var("LIBCLANG_PATH") {
-        set_env_variable("LIBCLANG_PATH", &libclang_path)?;
+/// Removes the block managed by espup from the contents.
+fn remove_managed_block(contents: &str) -> String {
+    match find_managed_block(contents) {
+        Some((start, end)) => format!("{}{}", &contents[..start], &contents[end..]),
+        None => contents.to_string(),
     }
+}
 
-    if let Ok(libclang_bin_path) = env::var("LIBCLANG_BIN_PATH") {
-        let libclang_bin_path: &str = &libclang_bin_path;
-        if !path.contains(libclang_bin_path) {
-            path = format!("{};{}", libclang_bin_path, path);
+/// Adds a block loading the export file to the shell profile, backing up the original profile first.
+pub fn install_profile(profile: &Path, export_file: &Path, shell: Shell) -> Result<(), Error> {
+    let contents = if profile.exists() {
+        let backup = profile.with_file_name(format!(
+            "{}.espup-backup",
+            profile.file_name().unwrap().to_string_lossy()
+        ));
+        // Keep the backup of the profile before espup first modified it
+        if !backup.exists() {
+            debug!(
+                "Backing up '{}' to '{}'",
+                profile.display(),
+                backup.display()
+            );
+            copy(profile, &backup)?;
         }
+        read_to_string(profile)?
+    } else {
+        let parent = profile.parent().unwrap();
+        create_dir_all(parent).map_err(|_| Error::CreateDirectory(parent.display().to_string()))?;
+        String::new()
+    };
+    write(
+        profile,
+        update_managed_block(
+            &contents,
+            &[shell.source_guarded(export_file)],
+            shell.comment(),
+        ),
+    )?;
+    info!("Updated '{}' to load the export file", profile.display());
+    Ok(())
+}
+
+/// Removes the block loading the export file from the shell profile.
+pub fn uninstall_profile(profile: &Path) -> Result<(), Error> {
+    if profile.exists() {
+        debug!("Removing espup block from '{}'", profile.display());
+        write(profile, remove_managed_block(&read_to_string(profile)?))?;
     }
+    Ok(())
+}
 
-    if let Ok(clang_path) = env::var("CLANG_PATH") {
-        let clang_path: &str = &clang_path;
-        if !path.contains(clang_path) {
-            path = format!("{};{}", clang_path, path);
+/// Removes the block managed by espup from a file, deleting the file if nothing else is left.
+pub fn uninstall_file(file: &Path) -> Result<(), Error> {
+    if file.exists() {
+        let contents = remove_managed_block(&read_to_string(file)?);
+        if contents.trim().is_empty() {
+            debug!("Removing '{}'", file.display());
+            remove_file(file)?;
+        } else {
+            debug!("Removing espup block from '{}'", file.display());
+            write(file, contents)?;
         }
     }
+    Ok(())
+}
 
+#[cfg(windows)]
+/// Sets the exported variables and prepends the exported directories to the `PATH` of the current user.
+pub fn set_env(exports: &[Export]) -> Result<(), Error> {
+    let (vars, paths) = normalize_exports(exports);
+    for (name, value) in vars {
+        set_env_variable(name, &value)?;
+    }
+    let mut path = env::var("PATH").unwrap_or_default();
+    // Prepend in reverse to keep the installation order
+    for bin_path in paths.iter().rev() {
+        if !path.contains(bin_path.as_str()) {
+            path = format!("{};{}", bin_path, path);
+        }
+    }
     set_env_variable("PATH", &path)?;
     Ok(())
 }
 
 #[cfg(windows)]
-/// Clean the environment for Windows.
-pub fn clean_env() -> Result<(), Error> {
+/// Clean the environment for Windows, including the variables and directories recorded in the exports.
+pub fn clean_env(exports: &[Export]) -> Result<(), Error> {
     delete_env_variable("LIBCLANG_PATH")?;
     delete_env_variable("CLANG_PATH")?;
+    let (vars, paths) = normalize_exports(exports);
+    for (name, _) in vars {
+        delete_env_variable(name)?;
+    }
+    for path in paths {
+        remove_path_entry(&path);
+    }
     if let Some(path) = env::var_os("PATH") {
         set_env_variable("PATH", &path.to_string_lossy())?;
     };
@@ -159,7 +896,12 @@ pub fn clean_env() -> Result<(), Error> {
 }
 
 /// Instructions to export the environment variables.
-pub fn print_post_install_msg(export_file: &Path) -> Result<(), Error> {
+#[cfg_attr(windows, allow(unused_variables))]
+pub fn print_post_install_msg(export_file: &Path, shell: Shell) -> Result<(), Error> {
+    // Keep stdout free of anything but the exports
+    if is_stdout(export_file) {
+        return Ok(());
+    }
     #[cfg(windows)]
     if cfg!(windows) {
         println!(
@@ -173,8 +915,8 @@ pub fn print_post_install_msg(export_file: &Path) -> Result<(), Error> {
     #[cfg(unix)]
     if cfg!(unix) {
         println!(
-            "\n\tTo get started, you need to set up some environment variables by running: '. {}'",
-            export_file.display()
+            "\n\tTo get started, you need to set up some environment variables by running: '{}'",
+            shell.source_command(export_file)
         );
         println!(
             "\tThis step must be done every time you open a new terminal.\n\t    See other methods for setting the environment in https://esp-rs.github.io/book/installation/riscv-and-xtensa.html#3-set-up-the-environment-variables",
@@ -185,7 +927,10 @@ pub fn print_post_install_msg(export_file: &Path) -> Result<(), Error> {
 
 #[cfg(test)]
 mod tests {
-    use crate::env::{create_export_file, get_export_file, DEFAULT_EXPORT_FILE};
+    use crate::env::{
+        create_export_file, format_exports, get_export_file, install_profile, remove_managed_block,
+        uninstall_file, uninstall_profile, update_managed_block, Export, Shell,
+    };
     use directories::BaseDirs;
     use std::{
         env::current_dir,
@@ -199,23 +944,28 @@ mod tests {
     fn test_get_export_file() {
         // No arg provided
         let home_dir = BaseDirs::new().unwrap().home_dir().to_path_buf();
-        let export_file = home_dir.join(DEFAULT_EXPORT_FILE);
-        assert!(matches!(get_export_file(None), Ok(export_file)));
+        let export_file = home_dir.join(Shell::Sh.export_file_name());
+        assert!(matches!(get_export_file(None, Shell::Sh), Ok(export_file)));
         // Relative path
         let current_dir = current_dir().unwrap();
         let export_file = current_dir.join("export.sh");
         assert!(matches!(
-            get_export_file(Some(PathBuf::from("export.sh"))),
+            get_export_file(Some(PathBuf::from("export.sh")), Shell::Sh),
             Ok(export_file)
         ));
         // Absolute path
         let export_file = PathBuf::from("/home/user/export.sh");
         assert!(matches!(
-            get_export_file(Some(PathBuf::from("/home/user/export.sh"))),
+            get_export_file(Some(PathBuf::from("/home/user/export.sh")), Shell::Sh),
             Ok(export_file)
         ));
         // Path is a directory instead of a file
-        assert!(get_export_file(Some(home_dir)).is_err());
+        assert!(get_export_file(Some(home_dir), Shell::Sh).is_err());
+        // Exports printed to stdout
+        assert_eq!(
+            get_export_file(Some(PathBuf::from("-")), Shell::Sh).unwrap(),
+            PathBuf::from("-")
+        );
     }
 
     #[test]
@@ -224,21 +974,339 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let export_file = temp_dir.path().join("export.sh");
         let exports = vec![
-            "export VAR1=value1".to_string(),
-            "export VAR2=value2".to_string(),
+            Export::Var("VAR1".to_string(), "value1".to_string()),
+            Export::Var("VAR2".to_string(), "value2".to_string()),
         ];
-        create_export_file(&export_file, &exports).unwrap();
-        let contents = read_to_string(export_file).unwrap();
-        assert_eq!(contents, "export VAR1=value1\nexport VAR2=value2\n");
+        create_export_file(
+            &export_file,
+            &format_exports(&exports, Shell::Sh),
+            Shell::Sh,
+        )
+        .unwrap();
+        let contents = read_to_string(&export_file).unwrap();
+        assert_eq!(
+            contents,
+            update_managed_block("", &format_exports(&exports, Shell::Sh), "#")
+        );
+
+        // Updates only the espup block, preserving user additions
+        std::fs::write(&export_file, format!("{contents}export USER_VAR=1\n")).unwrap();
+        let exports = vec![Export::Var("VAR1".to_string(), "value3".to_string())];
+        create_export_file(
+            &export_file,
+            &format_exports(&exports, Shell::Sh),
+            Shell::Sh,
+        )
+        .unwrap();
+        assert_eq!(
+            read_to_string(&export_file).unwrap(),
+            update_managed_block("", &format_exports(&exports, Shell::Sh), "#")
+                + "export USER_VAR=1\n"
+        );
 
         // Returns the correct error when it fails to create the export file (it already exists)
         let temp_dir = TempDir::new().unwrap();
         let export_file = temp_dir.path().join("export.sh");
         create_dir_all(&export_file).unwrap();
         let exports = vec![
-            "export VAR1=value1".to_string(),
-            "export VAR2=value2".to_string(),
+            Export::Var("VAR1".to_string(), "value1".to_string()),
+            Export::Var("VAR2".to_string(), "value2".to_string()),
+        ];
+        assert!(create_export_file(
+            &export_file,
+            &format_exports(&exports, Shell::Sh),
+            Shell::Sh
+        )
+        .is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_format_exports() {
+        let exports = vec![
+            Export::Path("/gcc/bin".to_string()),
+            Export::Var("LIBCLANG_PATH".to_string(), "/old/lib".to_string()),
+            Export::Path("/clang/bin/".to_string()),
+            Export::Var("LIBCLANG_PATH".to_string(), "/llvm/lib".to_string()),
+            Export::Path("/gcc/bin".to_string()),
         ];
-        assert!(create_export_file(&export_file, &exports).is_err());
+        assert_eq!(
+            format_exports(&exports, Shell::Sh),
+            vec![
+                "export LIBCLANG_PATH=\"/llvm/lib\"",
+                "case \":$PATH:\" in *\":/gcc/bin:/clang/bin:\"*) ;; *) export PATH=\"/gcc/bin:/clang/bin:$PATH\" ;; esac",
+            ]
+        );
+        assert_eq!(
+            format_exports(&exports, Shell::Csh),
+            vec![
+                "setenv LIBCLANG_PATH \"/llvm/lib\"",
+                "if ( \":${PATH}:\" !~ \"*:/gcc/bin:/clang/bin:*\" ) setenv PATH \"/gcc/bin:/clang/bin:${PATH}\"",
+            ]
+        );
+        assert_eq!(
+            format_exports(&exports, Shell::Elvish),
+            vec![
+                "set E:LIBCLANG_PATH = \"/llvm/lib\"",
+                "set paths = [(each {|p| if (not (has-value $paths $p)) { put $p } } [\"/gcc/bin\" \"/clang/bin\"]) $@paths]",
+            ]
+        );
+        assert_eq!(
+            format_exports(&exports, Shell::Fish),
+            vec![
+                "set -gx LIBCLANG_PATH \"/llvm/lib\"",
+                "fish_add_path --path --prepend \"/gcc/bin\" \"/clang/bin\"",
+            ]
+        );
+        assert_eq!(
+            format_exports(&exports, Shell::Nu),
+            vec![
+                "$env.LIBCLANG_PATH = \"/llvm/lib\"",
+                "$env.PATH = ($env.PATH | split row (char esep) | prepend [\"/gcc/bin\" \"/clang/bin\"] | uniq)",
+            ]
+        );
+        assert_eq!(
+            format_exports(&exports[..2], Shell::Cmd),
+            vec![
+                "@set \"LIBCLANG_PATH=/old/lib\"",
+                "@echo \":%PATH%:\" | find /i \":/gcc/bin:\" >nul || set \"PATH=/gcc/bin:%PATH%\"",
+            ]
+        );
+        assert!(format_exports(&[], Shell::Sh).is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_format_relocatable_exports() {
+        use crate::env::format_relocatable_exports;
+
+        let root = PathBuf::from("/rustup/toolchains/esp");
+        let exports = vec![
+            Export::Path("/rustup/toolchains/esp/gcc/bin".to_string()),
+            Export::Var(
+                "LIBCLANG_PATH".to_string(),
+                "/rustup/toolchains/esp/llvm/lib".to_string(),
+            ),
+            Export::Path("/opt/gcc/bin".to_string()),
+            Export::Path("/rustup/toolchains/esp-other/bin".to_string()),
+        ];
+        assert_eq!(
+            format_relocatable_exports(&exports, Shell::Sh, &root),
+            vec![
+                "export ESPUP_HOME=\"/rustup/toolchains/esp\"",
+                "export LIBCLANG_PATH=\"${ESPUP_HOME}/llvm/lib\"",
+                "case \":$PATH:\" in *\":${ESPUP_HOME}/gcc/bin:/opt/gcc/bin:/rustup/toolchains/esp-other/bin:\"*) ;; *) export PATH=\"${ESPUP_HOME}/gcc/bin:/opt/gcc/bin:/rustup/toolchains/esp-other/bin:$PATH\" ;; esac",
+            ]
+        );
+        let exports = &exports[..2];
+        assert_eq!(
+            format_relocatable_exports(exports, Shell::Csh, &root)[1],
+            "setenv LIBCLANG_PATH \"${ESPUP_HOME}/llvm/lib\""
+        );
+        assert_eq!(
+            format_relocatable_exports(exports, Shell::Elvish, &root)[2],
+            "set paths = [(each {|p| if (not (has-value $paths $p)) { put $p } } [$E:ESPUP_HOME\"/gcc/bin\"]) $@paths]"
+        );
+        assert_eq!(
+            format_relocatable_exports(exports, Shell::Fish, &root)[1],
+            "set -gx LIBCLANG_PATH \"$ESPUP_HOME/llvm/lib\""
+        );
+        assert_eq!(
+            format_relocatable_exports(exports, Shell::Nu, &root)[1],
+            "$env.LIBCLANG_PATH = $\"($env.ESPUP_HOME)/llvm/lib\""
+        );
+    }
+
+    #[test]
+    fn test_shell_from_name() {
+        assert_eq!(Shell::from_name("zsh"), Some(Shell::Sh));
+        assert_eq!(Shell::from_name("-bash"), Some(Shell::Sh));
+        assert_eq!(Shell::from_name("/usr/bin/fish"), Some(Shell::Fish));
+        assert_eq!(Shell::from_name("tcsh"), Some(Shell::Csh));
+        assert_eq!(Shell::from_name("pwsh.exe"), Some(Shell::PowerShell));
+        assert_eq!(Shell::from_name("CMD.EXE"), Some(Shell::Cmd));
+        assert_eq!(Shell::from_name("cargo"), None);
+    }
+
+    #[test]
+    fn test_update_managed_block() {
+        let lines = vec!["export VAR=\"value\"".to_string()];
+        let block = "# >>> espup >>>\nexport VAR=\"value\"\n# <<< espup <<<\n";
+        // Appended to empty contents, or to contents without a block
+        assert_eq!(update_managed_block("", &lines, "#"), block);
+        assert_eq!(
+            update_managed_block("use nix", &lines, "#"),
+            format!("use nix\n{block}")
+        );
+        // Replaces the existing block, keeping the user content
+        assert_eq!(
+            update_managed_block(
+                "use nix\n# >>> espup >>>\nexport VAR=\"old\"\n# <<< espup <<<\nexport FOO=1\n",
+                &lines,
+                "#"
+            ),
+            format!("use nix\n{block}export FOO=1\n")
+        );
+        // Batch files comment out the markers with `@rem`
+        assert_eq!(
+            remove_managed_block(&update_managed_block("@set FOO=1\n", &lines, "@rem")),
+            "@set FOO=1\n"
+        );
+        // Removes only the block
+        assert_eq!(
+            remove_managed_block(&format!("use nix\n{block}export FOO=1\n")),
+            "use nix\nexport FOO=1\n"
+        );
+        assert_eq!(remove_managed_block("use nix\n"), "use nix\n");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_format_dotenv() {
+        use crate::env::format_dotenv;
+
+        let exports = vec![
+            Export::Path("/gcc/bin".to_string()),
+            Export::Var("LIBCLANG_PATH".to_string(), "/llvm/lib".to_string()),
+        ];
+        assert_eq!(
+            format_dotenv(&exports, "/usr/bin:/gcc/bin"),
+            vec!["LIBCLANG_PATH=/llvm/lib", "PATH=/gcc/bin:/usr/bin"]
+        );
+    }
+
+    #[test]
+    fn test_install_profile() {
+        let temp_dir = TempDir::new().unwrap();
+        let profile = temp_dir.path().join(".bashrc");
+        std::fs::write(&profile, "alias ll='ls -l'\n").unwrap();
+        let export_file = temp_dir.path().join("export-esp.sh");
+
+        install_profile(&profile, &export_file, Shell::Sh).unwrap();
+        install_profile(&profile, &export_file, Shell::Sh).unwrap();
+        let contents = read_to_string(&profile).unwrap();
+        assert_eq!(contents.matches(". \"").count(), 1);
+        assert_eq!(
+            read_to_string(temp_dir.path().join(".bashrc.espup-backup")).unwrap(),
+            "alias ll='ls -l'\n"
+        );
+
+        uninstall_profile(&profile).unwrap();
+        assert_eq!(read_to_string(&profile).unwrap(), "alias ll='ls -l'\n");
+    }
+
+    #[test]
+    fn test_find_shadowed_tools() {
+        use crate::env::find_shadowed_tools;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_name = format!("xtensa-esp-elf-gcc{}", std::env::consts::EXE_SUFFIX);
+        let installed = temp_dir.path().join("esp").join("bin");
+        let other = temp_dir.path().join("other").join("bin");
+        for dir in [&installed, &other] {
+            create_dir_all(dir).unwrap();
+            std::fs::write(dir.join(&file_name), "").unwrap();
+        }
+        let exports = vec![Export::Path(installed.display().to_string())];
+
+        // Not in the PATH yet, it is prepended when loading the exports
+        let path = std::env::join_paths([&other]).unwrap();
+        assert!(find_shadowed_tools(&exports, &path.to_string_lossy()).is_empty());
+
+        let path = std::env::join_paths([&installed, &other]).unwrap();
+        assert!(find_shadowed_tools(&exports, &path.to_string_lossy()).is_empty());
+
+        let path = std::env::join_paths([&other, &installed]).unwrap();
+        assert_eq!(
+            find_shadowed_tools(&exports, &path.to_string_lossy()),
+            vec![("xtensa-esp-elf-gcc".to_string(), other.join(&file_name))]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_render_template() {
+        use crate::env::render_template;
+
+        let exports = vec![
+            Export::Path("/gcc/bin".to_string()),
+            Export::Path("/clang/bin".to_string()),
+            Export::Var("LIBCLANG_PATH".to_string(), "/llvm/lib".to_string()),
+        ];
+        let lines = format_exports(&exports, Shell::Sh);
+        assert_eq!(
+            render_template(
+                "# Company setup\nif [ -z \"$NO_ESP\" ]; then\n{{exports}}\nfi\n# {{ LIBCLANG_PATH }} {{path}}",
+                &lines,
+                &exports
+            )
+            .unwrap(),
+            vec![
+                "# Company setup",
+                "if [ -z \"$NO_ESP\" ]; then",
+                &lines[0],
+                &lines[1],
+                "fi",
+                "# /llvm/lib /gcc/bin:/clang/bin",
+            ]
+        );
+        assert!(render_template("{{CLANG_PATH}}", &lines, &exports).is_err());
+        assert!(render_template("{{exports", &lines, &exports).is_err());
+    }
+
+    #[test]
+    fn test_uninstall_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let exports = vec![Export::Path("bin".to_string())];
+        let export_file = temp_dir.path().join("export-esp.sh");
+        create_export_file(
+            &export_file,
+            &format_exports(&exports, Shell::Sh),
+            Shell::Sh,
+        )
+        .unwrap();
+        uninstall_file(&export_file).unwrap();
+        assert!(!export_file.exists());
+
+        let envrc = temp_dir.path().join(".envrc");
+        std::fs::write(&envrc, "dotenv\n").unwrap();
+        create_export_file(&envrc, &format_exports(&exports, Shell::Sh), Shell::Sh).unwrap();
+        uninstall_file(&envrc).unwrap();
+        assert_eq!(read_to_string(&envrc).unwrap(), "dotenv\n");
+    }
+
+    #[test]
+    fn test_write_github_env() {
+        use crate::env::write_github_env;
+
+        let temp_dir = TempDir::new().unwrap();
+        let github_env = temp_dir.path().join("github_env");
+        let github_path = temp_dir.path().join("github_path");
+        std::fs::write(&github_env, "EXISTING=1\n").unwrap();
+        let exports = vec![
+            Export::Path("bin".to_string()),
+            Export::Var("LIBCLANG_PATH".to_string(), "lib".to_string()),
+        ];
+        write_github_env(&exports, &github_env, &github_path).unwrap();
+        assert_eq!(
+            read_to_string(&github_env).unwrap(),
+            "EXISTING=1\nLIBCLANG_PATH=lib\n"
+        );
+        assert_eq!(read_to_string(&github_path).unwrap(), "bin\n");
+    }
+
+    #[test]
+    fn test_format_json() {
+        use crate::env::format_json;
+
+        let exports = vec![
+            Export::Path("bin".to_string()),
+            Export::Var("LIBCLANG_PATH".to_string(), "lib".to_string()),
+        ];
+        let json: serde_json::Value = serde_json::from_str(&format_json(&exports)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"variables": {"LIBCLANG_PATH": "lib"}, "path": ["bin"]})
+        );
     }
 }
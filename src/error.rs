@@ -2,23 +2,112 @@
 
 #[derive(Debug, miette::Diagnostic, thiserror::Error)]
 pub enum Error {
-    #[diagnostic(code(espup::toolchain::create_directory))]
+    #[diagnostic(
+        code(espup::aborted),
+        help("Pass `--yes` to confirm without a prompt, as prompts are declined in non-interactive mode")
+    )]
+    #[error("Cancelled by the user")]
+    Aborted,
+
+    #[diagnostic(
+        code(espup::toolchain::checksum_mismatch),
+        help("The download may be corrupted or incomplete. Please, try again and, if it persists, remove the espup cache directory")
+    )]
+    #[error("Checksum mismatch for '{0}': expected '{1}', found '{2}'")]
+    ChecksumMismatch(String, String, String),
+
+    #[diagnostic(
+        code(espup::events::conflicting_stdout),
+        help(
+            "Write the export file to a path with `--export-file`, or use `--message-format human`"
+        )
+    )]
+    #[error("The exports cannot be printed to stdout along with the JSON progress events")]
+    ConflictingStdout,
+
+    #[diagnostic(
+        code(espup::toolchain::create_directory),
+        help("Verify that you have write permissions in the parent directory")
+    )]
     #[error("Creating directory '{0}' failed")]
     CreateDirectory(String),
 
-    #[diagnostic(code(espup::toolchain::rust::query_github))]
+    #[diagnostic(
+        code(espup::toolchain::extract_file),
+        help("The archive may be corrupted or incomplete, please try again. On Windows, antivirus software may lock the extracted files")
+    )]
+    #[error("Failed to extract '{0}': {1}")]
+    ExtractFile(String, String),
+
+    #[diagnostic(code(espup::project::file_exists))]
+    #[error("'{0}' already exists. Please, remove it to generate a new one")]
+    FileExists(String),
+
+    #[diagnostic(
+        code(espup::toolchain::rust::query_github),
+        help("GitHub may be rate limiting the requests. Set the `GITHUB_TOKEN` environment variable to authenticate them")
+    )]
     #[error("Failed to query GitHub API")]
     GithubQuery,
 
-    #[diagnostic(code(espup::toolchain::rust::install_riscv_target))]
+    #[diagnostic(
+        code(espup::toolchain::rust::install_riscv_target),
+        help("Verify that rustup can install toolchains, e.g. by running `rustup toolchain install nightly`")
+    )]
     #[error("Failed to Install RISC-V targets for '{0}' toolchain")]
     InstallRiscvTarget(String),
 
+    #[diagnostic(
+        code(espup::events::ipc_connection),
+        help("Verify that the client is listening on the socket, or named pipe on Windows, before running espup")
+    )]
+    #[error("Failed to connect to '{0}': {1}")]
+    IpcConnection(String, String),
+
+    #[diagnostic(
+        code(espup::toolchain::interrupted),
+        help(
+            "The partially installed components were removed, run the same command again to resume"
+        )
+    )]
+    #[error("Installation interrupted")]
+    Interrupted,
+
+    #[diagnostic(code(espup::config::invalid_config))]
+    #[error("Invalid configuration file '{0}': {1}")]
+    InvalidConfig(String, String),
+
     #[diagnostic(code(espup::ivalid_destination))]
     #[error(
         "Invalid export file destination: '{0}'. Please, use an absolute or releative path (including the file and its extension)")]
     InvalidDestination(String),
 
+    #[diagnostic(code(espup::toolchain::gcc::invalid_gcc_version))]
+    #[error(
+        "Invalid GCC version '{0}'. Verify that the format is correct: '<major>.<minor>.<patch>_<date>' or '<year>r<revision>', and that the release exists in https://github.com/espressif/crosstool-NG/releases")]
+    InvalidGccVersion(String),
+
+    #[diagnostic(code(espup::project::invalid_json))]
+    #[error("Failed to parse '{0}': {1}")]
+    InvalidJson(String, String),
+
+    #[diagnostic(code(espup::toolchain::llvm::invalid_llvm_version))]
+    #[error(
+        "Invalid LLVM version '{0}'. Verify that the format is correct: 'esp-<major>.<minor>.<patch>-<date>', and that the release exists in https://github.com/espressif/llvm-project/releases")]
+    InvalidLlvmVersion(String),
+
+    #[diagnostic(code(espup::receipt::invalid_receipt))]
+    #[error("Invalid install receipt: '{0}'")]
+    InvalidReceipt(String),
+
+    #[diagnostic(code(espup::project::invalid_toml))]
+    #[error("Failed to parse '{0}': {1}")]
+    InvalidToml(String, String),
+
+    #[diagnostic(code(espup::env::invalid_template))]
+    #[error("Invalid placeholder '{0}' in the export file template. Supported placeholders are: '{{{{exports}}}}', '{{{{path}}}}' and the exported variables, e.g. '{{{{LIBCLANG_PATH}}}}'")]
+    InvalidTemplate(String),
+
     #[diagnostic(code(espup::toolchain::rust::invalid_version))]
     #[error(
         "Invalid toolchain version '{0}'. Verify that the format is correct: '<major>.<minor>.<patch>.<subpatch>' or '<major>.<minor>.<patch>', and that the release exists in https://github.com/esp-rs/rust-build/releases")]
@@ -27,18 +116,38 @@ pub enum Error {
     #[error(transparent)]
     IoError(#[from] std::io::Error),
 
+    #[diagnostic(code(espup::env::missing_environment))]
+    #[error(
+        "No environment recorded for the '{0}' toolchain. Please, install it with `espup install`"
+    )]
+    MissingEnvironment(String),
+
+    #[diagnostic(code(espup::toolchain::llvm::missing_llvm_nightly))]
+    #[error("No LLVM pre-release found in https://github.com/espressif/llvm-project/releases")]
+    MissingLlvmNightly,
+
     #[diagnostic(code(espup::toolchain::rust::missing_rust))]
     #[error("Rust is not installed. Please, install Rust via rustup: https://rustup.rs/")]
     MissingRust,
 
-    #[diagnostic(code(espup::remove_directory))]
+    #[diagnostic(
+        code(espup::remove_directory),
+        help("Verify that no other process, like an editor or a terminal, is using the directory")
+    )]
     #[error("Failed to remove '{0}'")]
     RemoveDirectory(String),
 
+    #[diagnostic(
+        code(espup::request_error),
+        help("Verify your network connection. Proxies are configured with the `HTTPS_PROXY` environment variable")
+    )]
     #[error(transparent)]
     RewquestError(#[from] reqwest::Error),
 
-    #[diagnostic(code(espup::toolchain::rust::rustup_detection_error))]
+    #[diagnostic(
+        code(espup::toolchain::rust::rustup_detection_error),
+        help("Verify that rustup is installed and in the `PATH`, see https://rustup.rs/")
+    )]
     #[error("Error detecting rustup: {0}")]
     RustupDetection(String),
 
@@ -46,7 +155,10 @@ pub enum Error {
     #[error("Failed to serialize json from string")]
     SerializeJson,
 
-    #[diagnostic(code(espup::toolchain::rust::uninstall_riscv_target))]
+    #[diagnostic(
+        code(espup::toolchain::rust::uninstall_riscv_target),
+        help("Verify that rustup works, e.g. by running `rustup target list --installed`")
+    )]
     #[error("Failed to uninstall RISC-V target")]
     UninstallRiscvTarget,
 
@@ -54,19 +166,88 @@ pub enum Error {
     #[error("Unsuported file extension: '{0}'")]
     UnsuportedFileExtension(String),
 
-    #[diagnostic(code(espup::host_triple::unsupported_host_triple))]
+    #[diagnostic(
+        code(espup::host_triple::unsupported_host_triple),
+        help("Use `--default-host` to install the toolchain of a supported host")
+    )]
     #[error("Host triple '{0}' is not supported")]
     UnsupportedHostTriple(String),
 
-    #[diagnostic(code(espup::targets::unsupported_target))]
+    #[diagnostic(
+        code(espup::targets::unsupported_target),
+        help("Supported targets are: esp32, esp32c2, esp32c3, esp32c6, esp32h2, esp32s2, esp32s3 and all")
+    )]
     #[error("Target '{0}' is not supported")]
     UnsupportedTarget(String),
 
-    #[diagnostic(code(espup::toolchain::rust::rust))]
+    #[diagnostic(
+        code(espup::toolchain::rust::rust),
+        help("Check the log file for the details. Running `espup uninstall` before installing again may help")
+    )]
     #[error("Failed to install 'rust' component of Xtensa Rust")]
     XtensaRust,
 
-    #[diagnostic(code(espup::toolchain::rust::rust_src))]
+    #[diagnostic(
+        code(espup::toolchain::rust::rust_src),
+        help("Check the log file for the details. Running `espup uninstall` before installing again may help")
+    )]
     #[error("Failed to install 'rust-src' component of Xtensa Rust")]
     XtensaRustSrc,
 }
+
+impl Error {
+    /// Gets the exit code of the error class:
+    /// - 1: other failures
+    /// - 2: invalid arguments or configuration
+    /// - 3: network errors
+    /// - 4: checksum mismatches
+    /// - 5: unsupported hosts
+    /// - 6: aborted or interrupted by the user
+    /// - 7: partial installations, when installing a component failed
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::InvalidConfig(..)
+            | Self::InvalidDestination(_)
+            | Self::InvalidGccVersion(_)
+            | Self::InvalidLlvmVersion(_)
+            | Self::InvalidTemplate(_)
+            | Self::InvalidVersion(_)
+            | Self::ConflictingStdout
+            | Self::UnsupportedTarget(_) => 2,
+            Self::GithubQuery | Self::RewquestError(_) => 3,
+            Self::ChecksumMismatch(..) => 4,
+            Self::UnsupportedHostTriple(_) => 5,
+            Self::Aborted | Self::Interrupted => 6,
+            Self::ExtractFile(..)
+            | Self::InstallRiscvTarget(_)
+            | Self::XtensaRust
+            | Self::XtensaRustSrc => 7,
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(Error::SerializeJson.exit_code(), 1);
+        assert_eq!(
+            Error::UnsupportedTarget("esp8266".to_string()).exit_code(),
+            2
+        );
+        assert_eq!(Error::GithubQuery.exit_code(), 3);
+        assert_eq!(
+            Error::ChecksumMismatch("a".to_string(), "b".to_string(), "c".to_string()).exit_code(),
+            4
+        );
+        assert_eq!(
+            Error::UnsupportedHostTriple("riscv64".to_string()).exit_code(),
+            5
+        );
+        assert_eq!(Error::Aborted.exit_code(), 6);
+        assert_eq!(Error::XtensaRust.exit_code(), 7);
+    }
+}
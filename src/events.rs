@@ -0,0 +1,186 @@
+//! Machine-readable progress events.
+
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use std::fs::OpenOptions;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::{
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+static JSON_EVENTS: AtomicBool = AtomicBool::new(false);
+
+static IPC: Mutex<Option<Ipc>> = Mutex::new(None);
+
+/// Connection to a client, like an IDE, receiving the events and answering the prompts.
+struct Ipc {
+    reader: Box<dyn BufRead + Send>,
+    writer: Box<dyn Write + Send>,
+}
+
+/// Answer of the client to a prompt.
+#[derive(Deserialize)]
+struct Answer {
+    answer: bool,
+}
+
+/// Progress event, printed to stdout as a line of JSON.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A phase started.
+    Started { phase: String },
+    /// Overall progress of the installation.
+    Progress { percent: u8 },
+    /// A phase finished successfully.
+    Finished { phase: String, duration_secs: f64 },
+    /// The installation failed.
+    Error { message: String },
+    /// A confirmation is required, answered by the client with `{"answer": true}` or `{"answer": false}`.
+    Prompt { message: String },
+}
+
+/// Enables printing the progress events.
+pub fn set_json_events(enabled: bool) {
+    JSON_EVENTS.store(enabled, Ordering::Relaxed);
+}
+
+/// Checks if the progress events are printed.
+pub fn is_json_events() -> bool {
+    JSON_EVENTS.load(Ordering::Relaxed)
+}
+
+/// Connects to the Unix socket, or named pipe on Windows, of a client.
+pub fn connect_ipc(path: &Path) -> Result<(), Error> {
+    let connection_error =
+        |e: std::io::Error| Error::IpcConnection(path.display().to_string(), e.to_string());
+    #[cfg(unix)]
+    let stream = UnixStream::connect(path).map_err(connection_error)?;
+    #[cfg(windows)]
+    let stream = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(connection_error)?;
+    let reader = stream.try_clone().map_err(connection_error)?;
+    *IPC.lock().unwrap() = Some(Ipc {
+        reader: Box::new(BufReader::new(reader)),
+        writer: Box::new(stream),
+    });
+    Ok(())
+}
+
+/// Prints a progress event if enabled, and sends it to the connected client.
+pub fn emit(event: Event) {
+    let line = serde_json::to_string(&event).unwrap();
+    if is_json_events() {
+        println!("{line}");
+    }
+    if let Some(ipc) = IPC.lock().unwrap().as_mut() {
+        let _ = writeln!(ipc.writer, "{line}");
+    }
+}
+
+/// Asks the connected client for confirmation, returns `None` if there is no client.
+///
+/// Answers that can not be read are considered a refusal.
+pub fn ipc_confirm(prompt: &str) -> Option<bool> {
+    let mut ipc = IPC.lock().unwrap();
+    let ipc = ipc.as_mut()?;
+    let event = Event::Prompt {
+        message: prompt.to_string(),
+    };
+    let mut answer = String::new();
+    let answered = writeln!(ipc.writer, "{}", serde_json::to_string(&event).unwrap())
+        .and_then(|_| ipc.reader.read_line(&mut answer));
+    Some(
+        answered.is_ok()
+            && serde_json::from_str::<Answer>(&answer).is_ok_and(|answer| answer.answer),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::Event;
+
+    #[test]
+    fn test_event_format() {
+        let events = [
+            Event::Started {
+                phase: "install".to_string(),
+            },
+            Event::Progress { percent: 50 },
+            Event::Finished {
+                phase: "llvm".to_string(),
+                duration_secs: 1.5,
+            },
+            Event::Error {
+                message: "failed".to_string(),
+            },
+            Event::Prompt {
+                message: "Continue?".to_string(),
+            },
+        ];
+        let lines: Vec<String> = events
+            .iter()
+            .map(|event| serde_json::to_string(event).unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                r#"{"event":"started","phase":"install"}"#,
+                r#"{"event":"progress","percent":50}"#,
+                r#"{"event":"finished","phase":"llvm","duration_secs":1.5}"#,
+                r#"{"event":"error","message":"failed"}"#,
+                r#"{"event":"prompt","message":"Continue?"}"#,
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ipc() {
+        use crate::events::{connect_ipc, emit, ipc_confirm};
+        use std::{
+            io::{BufRead, BufReader, Write},
+            os::unix::net::UnixListener,
+            thread,
+        };
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let socket = temp_dir.path().join("espup.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let client = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut lines = Vec::new();
+            for _ in 0..2 {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                lines.push(line);
+            }
+            writeln!(stream, r#"{{"answer":true}}"#).unwrap();
+            lines
+        });
+
+        assert_eq!(ipc_confirm("Continue?"), None);
+        connect_ipc(&socket).unwrap();
+        emit(Event::Progress { percent: 10 });
+        assert_eq!(ipc_confirm("Continue?"), Some(true));
+        assert_eq!(
+            client.join().unwrap(),
+            vec![
+                "{\"event\":\"progress\",\"percent\":10}\n",
+                "{\"event\":\"prompt\",\"message\":\"Continue?\"}\n",
+            ]
+        );
+    }
+}
@@ -1,44 +1,368 @@
 pub mod cli;
+pub mod config;
+pub mod deprecations;
 pub mod env;
 pub mod error;
+pub mod events;
 pub mod host_triple;
+pub mod project;
+pub mod receipt;
 pub mod targets;
 pub mod toolchain;
+pub mod wizard;
+
+pub mod color {
+    use crate::interactive::is_non_interactive;
+    use clap::ColorChoice;
+    use dialoguer::console::{set_colors_enabled, set_colors_enabled_stderr};
+    use miette::MietteHandlerOpts;
+    use std::{
+        env,
+        io::{stderr, IsTerminal},
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    static COLOR: AtomicBool = AtomicBool::new(false);
+
+    /// Sets when to use colored output, `auto` honors `NO_COLOR` and the non-interactive mode.
+    pub fn set_color(choice: ColorChoice) {
+        let color = match choice {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                env::var_os("NO_COLOR").map_or(true, |no_color| no_color.is_empty())
+                    && !is_non_interactive()
+                    && stderr().is_terminal()
+            }
+        };
+        COLOR.store(color, Ordering::Relaxed);
+        set_colors_enabled(color);
+        set_colors_enabled_stderr(color);
+        let _ = miette::set_hook(Box::new(move |_| {
+            Box::new(MietteHandlerOpts::new().color(color).build())
+        }));
+    }
+
+    /// Checks if the output is colored.
+    pub fn is_color_enabled() -> bool {
+        COLOR.load(Ordering::Relaxed)
+    }
+}
+
+pub mod interactive {
+    use crate::{error::Error, events::ipc_confirm};
+    use dialoguer::{theme::ColorfulTheme, Confirm};
+    use std::{
+        env,
+        io::{stdout, IsTerminal},
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    static NON_INTERACTIVE: AtomicBool = AtomicBool::new(false);
+
+    /// Sets the non-interactive mode, also enabled when stdout is not a terminal or `CI=true`.
+    pub fn set_non_interactive(non_interactive: bool) {
+        let ci = env::var("CI").is_ok_and(|ci| ci.eq_ignore_ascii_case("true"));
+        NON_INTERACTIVE.store(
+            non_interactive || ci || !stdout().is_terminal(),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Checks if prompts and animations are disabled.
+    pub fn is_non_interactive() -> bool {
+        NON_INTERACTIVE.load(Ordering::Relaxed)
+    }
+
+    /// Converts the errors of the prompts.
+    pub(crate) fn prompt_error(error: dialoguer::Error) -> Error {
+        match error {
+            dialoguer::Error::IO(e) => Error::IoError(e),
+        }
+    }
+
+    /// Asks for confirmation, assuming yes with `--yes` and no in non-interactive mode.
+    ///
+    /// The connected IPC client answers the prompt, if any.
+    pub fn confirm(prompt: &str, yes: bool) -> Result<bool, Error> {
+        if yes {
+            return Ok(true);
+        }
+        if let Some(answer) = ipc_confirm(prompt) {
+            return Ok(answer);
+        }
+        if is_non_interactive() {
+            return Ok(false);
+        }
+        Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .default(false)
+            .interact()
+            .map_err(prompt_error)
+    }
+}
 
 pub mod logging {
-    use env_logger::{Builder, Env, WriteStyle};
+    use crate::{color::is_color_enabled, deprecations::warn_deprecated, toolchain::get_cache_dir};
+    use std::{
+        env, fmt,
+        fs::{create_dir_all, File},
+        io::stderr,
+        path::{Path, PathBuf},
+        sync::{Mutex, OnceLock},
+        time::Instant,
+    };
+    use tracing::{warn, Event, Level, Subscriber};
+    use tracing_subscriber::{
+        filter::{EnvFilter, Targets},
+        fmt::{format::Writer, FmtContext, FormatEvent, FormatFields},
+        layer::SubscriberExt,
+        registry::LookupSpan,
+        util::SubscriberInitExt,
+        Layer,
+    };
+
+    /// Environment variable with the filter of the console logs, e.g. `espup::toolchain::llvm=debug` or `[download]=debug`.
+    pub const LOG_ENV: &str = "ESPUP_LOG";
+
+    static LOG_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+    /// Formats the logs as `[<level>]: <message>`, prefixed with the elapsed time, target and spans in the log file.
+    struct Format {
+        file: bool,
+        start: Instant,
+    }
+
+    impl<S, N> FormatEvent<S, N> for Format
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        N: for<'a> FormatFields<'a> + 'static,
+    {
+        fn format_event(
+            &self,
+            ctx: &FmtContext<'_, S, N>,
+            mut writer: Writer<'_>,
+            event: &Event<'_>,
+        ) -> fmt::Result {
+            let metadata = event.metadata();
+            let level = metadata.level().to_string().to_lowercase();
+            if self.file {
+                write!(
+                    writer,
+                    "[{:>8.3}s] [{}] {}",
+                    self.start.elapsed().as_secs_f64(),
+                    level,
+                    metadata.target()
+                )?;
+                for span in ctx
+                    .event_scope()
+                    .into_iter()
+                    .flat_map(|scope| scope.from_root())
+                {
+                    write!(writer, ":{}", span.name())?;
+                }
+                write!(writer, ": ")?;
+            } else if writer.has_ansi_escapes() {
+                let color = match *metadata.level() {
+                    Level::ERROR => 31,
+                    Level::WARN => 33,
+                    Level::INFO => 32,
+                    Level::DEBUG => 34,
+                    Level::TRACE => 36,
+                };
+                write!(writer, "[\x1b[{color}m{level}\x1b[0m]: ")?;
+            } else {
+                write!(writer, "[{level}]: ")?;
+            }
+            ctx.field_format().format_fields(writer.by_ref(), event)?;
+            writeln!(writer)
+        }
+    }
 
-    /// Initializes the logger
+    /// Sets the file receiving the debug logs, by default `espup.log` in the cache directory.
+    pub fn set_log_file(log_file: Option<PathBuf>) {
+        let _ = LOG_FILE.set(log_file.unwrap_or_else(|| get_cache_dir().join("espup.log")));
+    }
+
+    /// Gets the file receiving the debug logs.
+    pub fn get_log_file() -> Option<&'static Path> {
+        LOG_FILE.get().map(PathBuf::as_path)
+    }
+
+    /// Creates the log file, truncating the logs of previous runs.
+    fn create_log_file(log_file: &Path) -> std::io::Result<File> {
+        if let Some(parent) = log_file.parent() {
+            create_dir_all(parent)?;
+        }
+        File::create(log_file)
+    }
+
+    /// Gets the filter of the console logs from `ESPUP_LOG` or `RUST_LOG`, defaulting to the log level.
+    fn get_console_filter(log_level: &str) -> (EnvFilter, Option<String>) {
+        for var in [LOG_ENV, "RUST_LOG"] {
+            if let Ok(directives) = env::var(var) {
+                match EnvFilter::try_new(&directives) {
+                    Ok(filter) => return (filter, None),
+                    Err(e) => {
+                        return (
+                            EnvFilter::new(log_level),
+                            Some(format!("Invalid log filter '{directives}' in {var}: {e}")),
+                        )
+                    }
+                }
+            }
+        }
+        (EnvFilter::new(log_level), None)
+    }
+
+    /// Initializes the logger, with colored levels if the output is colored
     pub fn initialize_logger(log_level: &str) {
-        Builder::from_env(Env::default().default_filter_or(log_level))
-            .format(|buf, record| {
-                use std::io::Write;
-                writeln!(
-                    buf,
-                    "[{}]: {}",
-                    record.level().to_string().to_lowercase(),
-                    record.args()
-                )
-            })
-            .write_style(WriteStyle::Always)
+        let start = Instant::now();
+        let (console_filter, filter_error) = get_console_filter(log_level);
+        let console = tracing_subscriber::fmt::layer()
+            .event_format(Format { file: false, start })
+            .with_ansi(is_color_enabled())
+            .with_writer(stderr)
+            .with_filter(console_filter);
+        let mut file_error = None;
+        let file = get_log_file().and_then(|log_file| match create_log_file(log_file) {
+            Ok(file) => Some(
+                tracing_subscriber::fmt::layer()
+                    .event_format(Format { file: true, start })
+                    .with_ansi(false)
+                    .with_writer(Mutex::new(file))
+                    .with_filter(Targets::new().with_target(env!("CARGO_PKG_NAME"), Level::DEBUG)),
+            ),
+            Err(e) => {
+                file_error = Some(format!(
+                    "Failed to create the log file '{}': {}",
+                    log_file.display(),
+                    e
+                ));
+                None
+            }
+        });
+        tracing_subscriber::registry()
+            .with(console)
+            .with(file)
             .init();
+        for error in [filter_error, file_error].into_iter().flatten() {
+            warn!("{error}");
+        }
+        warn_deprecated();
     }
 }
 
 pub mod update {
-    use log::warn;
-    use std::time::Duration;
+    use crate::{
+        receipt::Receipt,
+        toolchain::{
+            get_cache_dir,
+            rust::{XtensaRust, RE_EXTENDED_SEMANTIC_VERSION},
+        },
+    };
+    use regex::Regex;
+    use std::{
+        fs::{create_dir_all, metadata, read_to_string, write},
+        sync::atomic::{AtomicBool, Ordering},
+        time::Duration,
+    };
+    use tracing::{debug, warn};
     use update_informer::{registry, Check};
 
-    /// Check crates.io for a new version of the application
+    /// Minimum interval between two checks for new versions.
+    const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+    static UPDATE_CHECK: AtomicBool = AtomicBool::new(true);
+
+    /// Enables or disables the checks for new versions.
+    pub fn set_update_check(enabled: bool) {
+        UPDATE_CHECK.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Check crates.io for a new version of the application, at most once a day
     pub fn check_for_update(name: &str, version: &str) {
-        // By setting the interval to 0 seconds we invalidate the cache with each
-        // invocation and ensure we're getting up-to-date results
+        if !UPDATE_CHECK.load(Ordering::Relaxed) {
+            return;
+        }
+        // The latest version is cached by update-informer between checks
         let informer =
-            update_informer::new(registry::Crates, name, version).interval(Duration::ZERO);
+            update_informer::new(registry::Crates, name, version).interval(CHECK_INTERVAL);
 
         if let Some(version) = informer.check_version().ok().flatten() {
             warn!("A new version of {name} ('{version}') is available");
         }
     }
+
+    /// Checks if the `latest` Xtensa Rust version is newer than the `installed` one.
+    fn is_newer_version(latest: &str, installed: &str) -> bool {
+        let re = Regex::new(RE_EXTENDED_SEMANTIC_VERSION).unwrap();
+        let parse = |version: &str| {
+            re.captures(version.trim()).map(|captures| {
+                ["major", "minor", "patch", "subpatch"].map(|name| {
+                    captures
+                        .name(name)
+                        .and_then(|number| number.as_str().parse::<u64>().ok())
+                        .unwrap_or_default()
+                })
+            })
+        };
+        matches!((parse(latest), parse(installed)), (Some(latest), Some(installed)) if latest > installed)
+    }
+
+    /// Checks for a newer release than the installed Xtensa Rust toolchain, querying GitHub at most once a day
+    pub async fn check_for_toolchain_update(toolchain_name: &str) {
+        if !UPDATE_CHECK.load(Ordering::Relaxed) {
+            return;
+        }
+        let Some(installed) = Receipt::load(toolchain_name)
+            .ok()
+            .and_then(|receipt| receipt.xtensa_rust)
+        else {
+            return;
+        };
+        let cache = get_cache_dir().join("latest-xtensa-rust");
+        let is_recent = metadata(&cache)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|elapsed| elapsed < CHECK_INTERVAL);
+        let latest = if is_recent {
+            read_to_string(&cache).ok()
+        } else {
+            match XtensaRust::get_latest_version().await {
+                Ok(latest) => {
+                    if create_dir_all(get_cache_dir()).is_ok() {
+                        let _ = write(&cache, &latest);
+                    }
+                    Some(latest)
+                }
+                Err(e) => {
+                    debug!("Failed to check for a new Xtensa Rust version: {e}");
+                    None
+                }
+            }
+        };
+        if let Some(latest) = latest.filter(|latest| is_newer_version(latest, &installed)) {
+            warn!(
+                "A new Xtensa Rust version ('{}') is available, run `espup update -a {}` to install it",
+                latest.trim(),
+                toolchain_name
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::update::is_newer_version;
+
+        #[test]
+        fn test_is_newer_version() {
+            assert!(is_newer_version("1.77.0.0\n", "1.76.0.1"));
+            assert!(is_newer_version("1.77.10.0", "1.77.9.0"));
+            assert!(!is_newer_version("1.77.0.0", "1.77.0.0"));
+            assert!(!is_newer_version("1.76.0.1", "1.77.0.0"));
+            assert!(!is_newer_version("invalid", "1.77.0.0"));
+        }
+    }
 }
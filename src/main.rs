@@ -1,34 +1,97 @@
-use clap::{CommandFactory, Parser};
+use clap::{parser::ValueSource, ArgMatches, ColorChoice, CommandFactory, FromArgMatches, Parser};
 #[cfg(windows)]
 use espup::env::clean_env;
 use espup::{
-    cli::{CompletionsOpts, InstallOpts, UninstallOpts},
-    logging::initialize_logger,
+    cli::{
+        CompletionsOpts, ContainerizeOpts, EnvOpts, InitProjectOpts, InstallOpts, UninstallOpts,
+    },
+    color::set_color,
+    config::{apply_config, get_config_files, load_config},
+    deprecations::record_deprecated,
+    env::{format_exports, format_json, uninstall_file, uninstall_profile, Shell},
+    error::Error,
+    events::{connect_ipc, emit, set_json_events, Event},
+    interactive::{confirm, is_non_interactive, set_non_interactive},
+    logging::{get_log_file, initialize_logger, set_log_file},
+    project::{
+        write_cargo_config, write_container_files, write_rust_toolchain, write_vscode_settings,
+    },
+    receipt::Receipt,
     toolchain::{
         gcc::uninstall_gcc_toolchains,
+        gdb::Gdb,
         install as toolchain_install,
         llvm::Llvm,
         remove_dir,
         rust::{get_rustup_home, XtensaRust},
         InstallMode,
     },
-    update::check_for_update,
+    update::{check_for_toolchain_update, check_for_update, set_update_check},
+    wizard::run_wizard,
 };
-use log::info;
 use miette::Result;
-use std::{env, io::stdout};
+use std::{
+    collections::HashSet, env, fs::remove_file, io::stdout, path::PathBuf, process::ExitCode,
+};
+use tracing::{info, warn};
 
 #[derive(Parser)]
 #[command(about, version)]
 struct Cli {
     #[command(subcommand)]
     subcommand: SubCommand,
+    /// When to use colored output.
+    ///
+    /// By default, the output is colored when stderr is a terminal, unless `NO_COLOR` is set or in non-interactive mode.
+    #[arg(
+        global = true,
+        help_heading = "Global Options",
+        long,
+        value_enum,
+        default_value_t = ColorChoice::Auto,
+        env = "ESPUP_COLOR"
+    )]
+    color: ColorChoice,
+    /// File receiving a detailed debug log, regardless of the log level.
+    ///
+    /// By default, `espup.log` in the espup cache directory is used.
+    #[arg(
+        global = true,
+        help_heading = "Global Options",
+        long,
+        env = "ESPUP_LOG_FILE"
+    )]
+    log_file: Option<PathBuf>,
+    /// Disables prompts and colored output, enabled by default when stdout is not a terminal or `CI=true`.
+    #[arg(
+        global = true,
+        help_heading = "Global Options",
+        long,
+        env = "ESPUP_NON_INTERACTIVE"
+    )]
+    non_interactive: bool,
+    /// Disables the checks for new versions of espup and of the installed Xtensa Rust toolchain.
+    ///
+    /// By default, they are checked at most once a day.
+    #[arg(
+        global = true,
+        help_heading = "Global Options",
+        long,
+        env = "ESPUP_NO_UPDATE_CHECK"
+    )]
+    no_update_check: bool,
 }
 
 #[derive(Parser)]
 pub enum SubCommand {
     /// Generate completions for the given shell.
     Completions(CompletionsOpts),
+    /// Generates a Dockerfile installing a pinned Espressif Rust ecosystem.
+    Containerize(ContainerizeOpts),
+    /// Prints the environment of an installed Espressif Rust ecosystem.
+    Env(EnvOpts),
+    /// Generates the configuration of a project for the given chip.
+    InitProject(InitProjectOpts),
     /// Installs Espressif Rust ecosystem.
     // We use a Box here to make clippy happy (see https://rust-lang.github.io/rust-clippy/master/index.html#large_enum_variant)
     Install(Box<InstallOpts>),
@@ -52,46 +115,234 @@ async fn completions(args: CompletionsOpts) -> Result<()> {
     Ok(())
 }
 
-/// Installs or updates the Rust for ESP chips environment
-async fn install(args: InstallOpts, install_mode: InstallMode) -> Result<()> {
+/// Generates the container files of a project
+async fn containerize(args: ContainerizeOpts) -> Result<()> {
     initialize_logger(&args.log_level);
     check_for_update(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
 
-    toolchain_install(args, install_mode).await?;
+    let toolchain_version = match &args.toolchain_version {
+        Some(version) if args.skip_version_parse => version.clone(),
+        Some(version) => XtensaRust::parse_version(version).await?,
+        None => XtensaRust::get_latest_version().await?,
+    };
+    info!(
+        "Generating container files for Xtensa Rust {}",
+        toolchain_version
+    );
+    write_container_files(
+        &args.path,
+        &args.targets,
+        args.std,
+        &toolchain_version,
+        args.devcontainer,
+    )?;
+
+    info!("Container files successfully generated!");
+    Ok(())
+}
+
+/// Prints the environment recorded when installing
+async fn env(args: EnvOpts) -> Result<()> {
+    initialize_logger(&args.log_level);
+    check_for_toolchain_update(&args.name).await;
+
+    let receipt = Receipt::load(&args.name)?;
+    if receipt.exports.is_empty() {
+        return Err(Error::MissingEnvironment(args.name).into());
+    }
+    match args.format.as_str() {
+        "json" => println!("{}", format_json(&receipt.exports)),
+        _ => {
+            let shell = args.shell.unwrap_or_else(Shell::detect);
+            for line in format_exports(&receipt.exports, shell) {
+                println!("{line}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Generates the configuration of a project
+async fn init_project(args: InitProjectOpts) -> Result<()> {
+    initialize_logger(&args.log_level);
+    check_for_toolchain_update(&args.name).await;
+
+    info!("Configuring the project for {}", args.target);
+    write_cargo_config(&args.path, args.target, args.std)?;
+    write_rust_toolchain(
+        &args.path,
+        args.target,
+        args.std,
+        &args.name,
+        &args.nightly_version,
+    )?;
+    if args.vscode {
+        let receipt = Receipt::load(&args.name)?;
+        if receipt.exports.is_empty() {
+            warn!(
+                "No environment recorded for the '{}' toolchain, the VS Code settings will not include it",
+                args.name
+            );
+        }
+        write_vscode_settings(&args.path, args.target, args.std, &receipt.exports)?;
+    }
+
+    info!("Project successfully configured!");
+    Ok(())
+}
+
+/// Options prompted by the installation wizard.
+const WIZARD_OPTIONS: &[&str] = &["export_file", "name", "std", "targets"];
+
+/// Checks that none of the options prompted by the wizard is set, either in the command line, the
+/// environment or a configuration file.
+fn is_wizard_needed(matches: &ArgMatches) -> bool {
+    WIZARD_OPTIONS.iter().all(|id| {
+        matches!(
+            matches.value_source(id),
+            None | Some(ValueSource::DefaultValue)
+        )
+    })
+}
+
+/// Installs or updates the Rust for ESP chips environment
+async fn install(args: InstallOpts, install_mode: InstallMode, wizard: bool) -> Result<()> {
+    initialize_logger(if args.quiet { "warn" } else { &args.log_level });
+    check_for_update(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+
+    let args = if wizard && !is_non_interactive() {
+        run_wizard(args)?.ok_or(Error::Aborted)?
+    } else {
+        args
+    };
+
+    set_json_events(args.message_format == "json");
+    if let Some(ipc) = &args.ipc {
+        connect_ipc(ipc)?;
+    }
+    if let Err(e) = toolchain_install(args, install_mode).await {
+        emit(Event::Error {
+            message: e.to_string(),
+        });
+        return Err(e);
+    }
     Ok(())
 }
 
 /// Uninstalls the Rust for ESP chips environment
 async fn uninstall(args: UninstallOpts) -> Result<()> {
-    initialize_logger(&args.log_level);
+    initialize_logger(if args.quiet { "warn" } else { &args.log_level });
     check_for_update(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
 
+    let toolchain_dir = get_rustup_home().join("toolchains").join(&args.name);
+    if !confirm(
+        &format!(
+            "Uninstall the '{}' toolchain from '{}'?",
+            args.name,
+            toolchain_dir.display()
+        ),
+        args.yes,
+    )? {
+        return Err(Error::Aborted.into());
+    }
+
     info!("Uninstalling the Espressif Rust ecosystem");
-    let toolchain_dir = get_rustup_home().join("toolchains").join(args.name);
+    let receipt = Receipt::load(&args.name)?;
+    #[cfg(windows)]
+    let modified_env =
+        toolchain_dir.exists() || !receipt.gcc.is_empty() || !receipt.exports.is_empty();
 
     if toolchain_dir.exists() {
         Llvm::uninstall(&toolchain_dir).await?;
 
-        uninstall_gcc_toolchains(&toolchain_dir).await?;
+        uninstall_gcc_toolchains(&toolchain_dir, &receipt).await?;
+
+        Gdb::uninstall(&toolchain_dir).await?;
 
         XtensaRust::uninstall(&toolchain_dir).await?;
 
         remove_dir(&toolchain_dir).await?;
+    } else if !receipt.gcc.is_empty() {
+        // GCC toolchains installed in a custom location
+        uninstall_gcc_toolchains(&toolchain_dir, &receipt).await?;
+    }
 
-        #[cfg(windows)]
-        clean_env()?;
+    #[cfg(windows)]
+    if modified_env {
+        clean_env(&receipt.exports)?;
     }
+    for file in &receipt.files {
+        uninstall_file(file)?;
+    }
+    for dotenv_file in receipt.dotenv_files.iter().filter(|f| f.exists()) {
+        remove_file(dotenv_file).map_err(Error::IoError)?;
+    }
+    for profile in &receipt.profiles {
+        uninstall_profile(profile)?;
+    }
+    Receipt::remove(&args.name)?;
 
     info!("Uninstallation successfully completed!");
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    match Cli::parse().subcommand {
+/// Gets the environment variables of all the options.
+fn get_option_vars() -> HashSet<String> {
+    let command = Cli::command();
+    std::iter::once(&command)
+        .chain(command.get_subcommands())
+        .flat_map(|command| command.get_arguments())
+        .filter_map(|arg| arg.get_env())
+        .map(|var| var.to_string_lossy().to_string())
+        .collect()
+}
+
+/// Runs the subcommand.
+async fn run() -> Result<()> {
+    let command = Cli::command();
+    let matches = command.clone().get_matches();
+    record_deprecated(&command, &matches);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    set_non_interactive(cli.non_interactive);
+    set_color(cli.color);
+    set_log_file(cli.log_file);
+    set_update_check(!cli.no_update_check);
+    let wizard = matches
+        .subcommand_matches("install")
+        .is_some_and(is_wizard_needed);
+
+    match cli.subcommand {
         SubCommand::Completions(args) => completions(args).await,
-        SubCommand::Install(args) => install(*args, InstallMode::Install).await,
-        SubCommand::Update(args) => install(*args, InstallMode::Update).await,
+        SubCommand::Containerize(args) => containerize(args).await,
+        SubCommand::Env(args) => env(args).await,
+        SubCommand::InitProject(args) => init_project(args).await,
+        SubCommand::Install(args) => install(*args, InstallMode::Install, wizard).await,
+        SubCommand::Update(args) => install(*args, InstallMode::Update, false).await,
         SubCommand::Uninstall(args) => uninstall(args).await,
     }
 }
+
+/// Applies the configuration files and runs the subcommand.
+fn try_main() -> Result<()> {
+    // The configuration is applied through environment variables, which must be set before the
+    // runtime starts its worker threads
+    apply_config(load_config(&get_config_files(), &get_option_vars())?);
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(Error::IoError)?
+        .block_on(run())
+}
+
+fn main() -> ExitCode {
+    match try_main() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(report) => {
+            eprintln!("Error: {report:?}");
+            if let Some(log_file) = get_log_file().filter(|log_file| log_file.exists()) {
+                eprintln!("A detailed log is available at '{}'", log_file.display());
+            }
+            ExitCode::from(report.downcast_ref::<Error>().map_or(1, Error::exit_code))
+        }
+    }
+}
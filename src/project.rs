@@ -0,0 +1,453 @@
+//! Project configuration generation.
+
+use crate::{
+    env::{normalize_exports, Export},
+    error::Error,
+    targets::Target,
+    toolchain::gcc::DEFAULT_GCC_RELEASE,
+};
+use miette::Result;
+use serde_json::{Map, Value};
+use std::{
+    collections::HashSet,
+    fs::{create_dir_all, read_to_string, write},
+    path::Path,
+};
+use toml_edit::{value, Array, Document, Item, Table};
+use tracing::{debug, info};
+
+/// Runner used to flash and monitor the project.
+const RUNNER: &str = "espflash flash --monitor";
+
+/// Gets the Rust target triple of a chip.
+pub fn get_target_triple(target: Target, std: bool) -> &'static str {
+    match (target, std) {
+        (Target::ESP32, false) => "xtensa-esp32-none-elf",
+        (Target::ESP32, true) => "xtensa-esp32-espidf",
+        (Target::ESP32S2, false) => "xtensa-esp32s2-none-elf",
+        (Target::ESP32S2, true) => "xtensa-esp32s2-espidf",
+        (Target::ESP32S3, false) => "xtensa-esp32s3-none-elf",
+        (Target::ESP32S3, true) => "xtensa-esp32s3-espidf",
+        (Target::ESP32C2 | Target::ESP32C3, false) => "riscv32imc-unknown-none-elf",
+        (Target::ESP32C2 | Target::ESP32C3, true) => "riscv32imc-esp-espidf",
+        (Target::ESP32C6 | Target::ESP32H2, false) => "riscv32imac-unknown-none-elf",
+        (Target::ESP32C6 | Target::ESP32H2, true) => "riscv32imac-esp-espidf",
+    }
+}
+
+/// Gets the `rustflags` required by a chip.
+fn get_rustflags(target: Target, std: bool) -> &'static [&'static str] {
+    if std {
+        &["--cfg", "espidf_time64"]
+    } else if target.is_xtensa() {
+        &["-C", "link-arg=-Tlinkall.x", "-C", "link-arg=-nostartfiles"]
+    } else {
+        &["-C", "link-arg=-Tlinkall.x", "-C", "force-frame-pointers"]
+    }
+}
+
+/// Creates an array value from a list of strings.
+fn array(items: &[&str]) -> Item {
+    value(items.iter().copied().collect::<Array>())
+}
+
+/// Gets a table of a document, creating it when missing.
+fn get_table<'a>(table: &'a mut Table, key: &str, implicit: bool) -> Result<&'a mut Table, Error> {
+    let item = table.entry(key).or_insert_with(|| {
+        let mut new = Table::new();
+        new.set_implicit(implicit);
+        Item::Table(new)
+    });
+    item.as_table_mut()
+        .ok_or_else(|| Error::InvalidToml(key.to_string(), "expected a table".to_string()))
+}
+
+/// Parses the contents of a TOML file.
+fn parse_toml(contents: &str, path: &Path) -> Result<Document, Error> {
+    contents
+        .parse::<Document>()
+        .map_err(|e| Error::InvalidToml(path.display().to_string(), e.to_string()))
+}
+
+/// Updates a `.cargo/config.toml` document for the given chip, keeping unrelated settings.
+pub fn update_cargo_config(doc: &mut Document, target: Target, std: bool) -> Result<(), Error> {
+    let triple = get_target_triple(target, std);
+
+    let build = get_table(doc.as_table_mut(), "build", false)?;
+    build["target"] = value(triple);
+
+    let targets = get_table(doc.as_table_mut(), "target", true)?;
+    let triple_table = get_table(targets, triple, false)?;
+    if std {
+        triple_table["linker"] = value("ldproxy");
+    }
+    triple_table["runner"] = value(RUNNER);
+    triple_table["rustflags"] = array(get_rustflags(target, std));
+
+    if std {
+        let env = get_table(doc.as_table_mut(), "env", false)?;
+        env["MCU"] = value(target.to_string());
+    }
+
+    let unstable = get_table(doc.as_table_mut(), "unstable", false)?;
+    unstable["build-std"] = if std {
+        array(&["std", "panic_abort"])
+    } else {
+        array(&["core"])
+    };
+
+    Ok(())
+}
+
+/// Updates a `rust-toolchain.toml` document to pin the toolchain of the given chip.
+///
+/// Xtensa chips use the Xtensa Rust toolchain, while RISC-V chips use the nightly channel.
+pub fn update_rust_toolchain(
+    doc: &mut Document,
+    target: Target,
+    std: bool,
+    name: &str,
+    nightly_version: &str,
+) -> Result<(), Error> {
+    let toolchain = get_table(doc.as_table_mut(), "toolchain", false)?;
+    if target.is_xtensa() {
+        toolchain["channel"] = value(name);
+        toolchain.remove("components");
+        toolchain.remove("targets");
+    } else {
+        toolchain["channel"] = value(nightly_version);
+        toolchain["components"] = array(&["rust-src"]);
+        if std {
+            toolchain.remove("targets");
+        } else {
+            toolchain["targets"] = array(&[get_target_triple(target, std)]);
+        }
+    }
+    Ok(())
+}
+
+/// Updates the VS Code settings of a project so rust-analyzer checks the given chip with the
+/// installed environment.
+pub fn update_vscode_settings(
+    settings: &mut Value,
+    target: Target,
+    std: bool,
+    exports: &[Export],
+) -> Result<(), Error> {
+    let settings = settings.as_object_mut().ok_or_else(|| {
+        Error::InvalidJson(
+            "settings.json".to_string(),
+            "expected an object".to_string(),
+        )
+    })?;
+    settings.insert(
+        "rust-analyzer.cargo.target".to_string(),
+        get_target_triple(target, std).into(),
+    );
+    settings.insert("rust-analyzer.check.allTargets".to_string(), false.into());
+    let (vars, _) = normalize_exports(exports);
+    if !vars.is_empty() {
+        let extra_env: Map<String, Value> = vars
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.into()))
+            .collect();
+        settings.insert("rust-analyzer.cargo.extraEnv".to_string(), extra_env.into());
+    }
+    Ok(())
+}
+
+/// Formats a Dockerfile installing the pinned Espressif Rust ecosystem with espup.
+pub fn format_dockerfile(targets: &HashSet<Target>, std: bool, toolchain_version: &str) -> String {
+    let version = env!("CARGO_PKG_VERSION");
+    let mut targets: Vec<String> = targets.iter().map(|t| t.to_string()).collect();
+    targets.sort();
+
+    let mut packages = vec!["libudev-dev", "pkg-config"];
+    let mut crates = vec!["espflash"];
+    let mut install_args = format!(
+        "--targets {} --toolchain-version {toolchain_version}",
+        targets.join(",")
+    );
+    if std {
+        packages.extend(["git", "python3", "python3-venv"]);
+        crates.push("ldproxy");
+        install_args.push_str(" --std --build-tools");
+    } else {
+        install_args.push_str(&format!(" --gcc-version {DEFAULT_GCC_RELEASE}"));
+    }
+
+    format!(
+        r#"# Generated by espup {version}
+FROM rust:bookworm
+
+ENV SHELL=/bin/bash
+RUN apt-get update \
+    && apt-get install -y --no-install-recommends {packages} \
+    && rm -rf /var/lib/apt/lists/*
+
+RUN cargo install espup --version {version} --locked
+RUN espup install {install_args} --export-file $HOME/export-esp.sh --profile-install
+RUN . $HOME/export-esp.sh && cargo install {crates} --locked
+
+# Non-interactive shells don't read the profile, load the exports for every command
+ENTRYPOINT ["/bin/bash", "-c", ". $HOME/export-esp.sh && exec \"$@\"", "--"]
+CMD ["/bin/bash"]
+"#,
+        packages = packages.join(" "),
+        crates = crates.join(" "),
+    )
+}
+
+/// Formats a `devcontainer.json` building the generated Dockerfile.
+pub fn format_devcontainer() -> String {
+    let devcontainer = serde_json::json!({
+        "name": "Espressif Rust",
+        "build": {
+            "dockerfile": "../Dockerfile",
+            "context": "..",
+        },
+        "customizations": {
+            "vscode": {
+                "extensions": ["rust-lang.rust-analyzer"],
+            },
+        },
+    });
+    serde_json::to_string_pretty(&devcontainer).unwrap() + "\n"
+}
+
+/// Writes a new file, failing if it already exists.
+fn write_new_file(path: &Path, contents: &str) -> Result<(), Error> {
+    if path.exists() {
+        return Err(Error::FileExists(path.display().to_string()));
+    }
+    info!("Creating '{}'", path.display());
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent).map_err(|_| Error::CreateDirectory(parent.display().to_string()))?;
+    }
+    write(path, contents)?;
+    Ok(())
+}
+
+/// Writes the Dockerfile and, optionally, the `devcontainer.json` of a project.
+pub fn write_container_files(
+    project: &Path,
+    targets: &HashSet<Target>,
+    std: bool,
+    toolchain_version: &str,
+    devcontainer: bool,
+) -> Result<(), Error> {
+    write_new_file(
+        &project.join("Dockerfile"),
+        &format_dockerfile(targets, std, toolchain_version),
+    )?;
+    if devcontainer {
+        write_new_file(
+            &project.join(".devcontainer").join("devcontainer.json"),
+            &format_devcontainer(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Reads a TOML file, or creates an empty document if the file does not exist yet.
+fn read_toml(path: &Path) -> Result<Document, Error> {
+    let contents = if path.exists() {
+        info!("Updating '{}'", path.display());
+        read_to_string(path)?
+    } else {
+        info!("Creating '{}'", path.display());
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)
+                .map_err(|_| Error::CreateDirectory(parent.display().to_string()))?;
+        }
+        String::new()
+    };
+    parse_toml(&contents, path)
+}
+
+/// Writes or updates the `.cargo/config.toml` of a project.
+pub fn write_cargo_config(project: &Path, target: Target, std: bool) -> Result<(), Error> {
+    let config = project.join(".cargo").join("config.toml");
+    debug!("Cargo config path: {}", config.display());
+
+    let mut doc = read_toml(&config)?;
+    update_cargo_config(&mut doc, target, std)?;
+    write(&config, doc.to_string())?;
+    Ok(())
+}
+
+/// Writes or updates the `.vscode/settings.json` of a project.
+pub fn write_vscode_settings(
+    project: &Path,
+    target: Target,
+    std: bool,
+    exports: &[Export],
+) -> Result<(), Error> {
+    let settings_path = project.join(".vscode").join("settings.json");
+    debug!("VS Code settings path: {}", settings_path.display());
+
+    let mut settings = if settings_path.exists() {
+        info!("Updating '{}'", settings_path.display());
+        serde_json::from_str(&read_to_string(&settings_path)?)
+            .map_err(|e| Error::InvalidJson(settings_path.display().to_string(), e.to_string()))?
+    } else {
+        info!("Creating '{}'", settings_path.display());
+        let parent = settings_path.parent().unwrap();
+        create_dir_all(parent).map_err(|_| Error::CreateDirectory(parent.display().to_string()))?;
+        Value::Object(Map::new())
+    };
+    update_vscode_settings(&mut settings, target, std, exports)?;
+    write(
+        &settings_path,
+        serde_json::to_string_pretty(&settings).unwrap() + "\n",
+    )?;
+    Ok(())
+}
+
+/// Writes or updates the `rust-toolchain.toml` of a project.
+pub fn write_rust_toolchain(
+    project: &Path,
+    target: Target,
+    std: bool,
+    name: &str,
+    nightly_version: &str,
+) -> Result<(), Error> {
+    let rust_toolchain = project.join("rust-toolchain.toml");
+    debug!("Rust toolchain file path: {}", rust_toolchain.display());
+
+    let mut doc = read_toml(&rust_toolchain)?;
+    update_rust_toolchain(&mut doc, target, std, name, nightly_version)?;
+    write(&rust_toolchain, doc.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        env::Export,
+        project::{
+            format_dockerfile, get_target_triple, parse_toml, update_cargo_config,
+            update_rust_toolchain, update_vscode_settings,
+        },
+        targets::Target,
+        toolchain::gcc::DEFAULT_GCC_RELEASE,
+    };
+    use std::{collections::HashSet, path::Path};
+    use toml_edit::Document;
+
+    fn cargo_config(contents: &str, target: Target, std: bool) -> String {
+        let mut doc = parse_toml(contents, Path::new("config.toml")).unwrap();
+        update_cargo_config(&mut doc, target, std).unwrap();
+        doc.to_string()
+    }
+
+    #[test]
+    fn test_get_target_triple() {
+        assert_eq!(
+            get_target_triple(Target::ESP32S3, false),
+            "xtensa-esp32s3-none-elf"
+        );
+        assert_eq!(
+            get_target_triple(Target::ESP32C6, true),
+            "riscv32imac-esp-espidf"
+        );
+    }
+
+    #[test]
+    fn test_update_cargo_config() {
+        assert_eq!(
+            cargo_config("", Target::ESP32C3, false),
+            r#"[build]
+target = "riscv32imc-unknown-none-elf"
+
+[target.riscv32imc-unknown-none-elf]
+runner = "espflash flash --monitor"
+rustflags = ["-C", "link-arg=-Tlinkall.x", "-C", "force-frame-pointers"]
+
+[unstable]
+build-std = ["core"]
+"#
+        );
+
+        let existing =
+            "[alias]\nflash = \"run --release\"\n\n[build]\ntarget = \"xtensa-esp32-none-elf\"\n";
+        let updated = cargo_config(existing, Target::ESP32, true);
+        assert!(updated.starts_with("[alias]\nflash = \"run --release\"\n"));
+        assert!(updated.contains("target = \"xtensa-esp32-espidf\""));
+        assert!(updated.contains("[target.xtensa-esp32-espidf]\nlinker = \"ldproxy\""));
+        assert!(updated.contains("MCU = \"esp32\""));
+        assert!(updated.contains("build-std = [\"std\", \"panic_abort\"]"));
+
+        assert!(parse_toml("[build", Path::new("config.toml")).is_err());
+
+        let mut doc = "build = 1".parse::<Document>().unwrap();
+        assert!(update_cargo_config(&mut doc, Target::ESP32, false).is_err());
+    }
+
+    #[test]
+    fn test_update_rust_toolchain() {
+        let mut doc = parse_toml("", Path::new("rust-toolchain.toml")).unwrap();
+        update_rust_toolchain(&mut doc, Target::ESP32S2, false, "esp", "nightly").unwrap();
+        assert_eq!(doc.to_string(), "[toolchain]\nchannel = \"esp\"\n");
+
+        update_rust_toolchain(
+            &mut doc,
+            Target::ESP32C3,
+            false,
+            "esp",
+            "nightly-2024-01-01",
+        )
+        .unwrap();
+        assert_eq!(
+            doc.to_string(),
+            r#"[toolchain]
+channel = "nightly-2024-01-01"
+components = ["rust-src"]
+targets = ["riscv32imc-unknown-none-elf"]
+"#
+        );
+
+        update_rust_toolchain(&mut doc, Target::ESP32, true, "esp", "nightly").unwrap();
+        assert_eq!(doc.to_string(), "[toolchain]\nchannel = \"esp\"\n");
+    }
+
+    #[test]
+    fn test_update_vscode_settings() {
+        let mut settings = serde_json::json!({ "editor.formatOnSave": true });
+        let exports = vec![
+            Export::Var("LIBCLANG_PATH".to_string(), "/esp/clang/lib".to_string()),
+            Export::Path("/esp/gcc/bin".to_string()),
+        ];
+        update_vscode_settings(&mut settings, Target::ESP32S3, false, &exports).unwrap();
+        assert_eq!(
+            settings,
+            serde_json::json!({
+                "editor.formatOnSave": true,
+                "rust-analyzer.cargo.target": "xtensa-esp32s3-none-elf",
+                "rust-analyzer.check.allTargets": false,
+                "rust-analyzer.cargo.extraEnv": { "LIBCLANG_PATH": "/esp/clang/lib" },
+            })
+        );
+
+        assert!(
+            update_vscode_settings(&mut serde_json::json!([]), Target::ESP32, false, &[]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_format_dockerfile() {
+        let targets = HashSet::from([Target::ESP32S3, Target::ESP32C3]);
+        let dockerfile = format_dockerfile(&targets, false, "1.77.0.0");
+        assert!(dockerfile.contains(&format!(
+            "RUN espup install --targets esp32c3,esp32s3 --toolchain-version 1.77.0.0 --gcc-version {DEFAULT_GCC_RELEASE} --export-file $HOME/export-esp.sh --profile-install"
+        )));
+        assert!(dockerfile.contains("RUN . $HOME/export-esp.sh && cargo install espflash --locked"));
+        assert!(dockerfile.contains(
+            r#"ENTRYPOINT ["/bin/bash", "-c", ". $HOME/export-esp.sh && exec \"$@\"", "--"]"#
+        ));
+
+        let dockerfile = format_dockerfile(&targets, true, "1.77.0.0");
+        assert!(dockerfile.contains("--toolchain-version 1.77.0.0 --std --build-tools"));
+        assert!(dockerfile.contains("&& cargo install espflash ldproxy --locked"));
+    }
+}
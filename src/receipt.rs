@@ -0,0 +1,168 @@
+//! Install receipt, recording what espup installed so it can be removed later.
+
+use crate::{env::Export, error::Error};
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{create_dir_all, read_to_string, remove_file, write},
+    path::PathBuf,
+};
+use tracing::debug;
+
+/// Installed GCC toolchain.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct GccReceipt {
+    /// GCC Toolchain architecture.
+    pub arch: String,
+    /// Directory where the toolchain is installed.
+    pub path: PathBuf,
+    /// Directory added to the `PATH`.
+    pub bin_path: String,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Receipt {
+    /// Environment changes required by the installed tools.
+    #[serde(default)]
+    pub exports: Vec<Export>,
+    /// Dotenv files written by espup.
+    #[serde(default)]
+    pub dotenv_files: Vec<PathBuf>,
+    /// Files with a block managed by espup, such as the export file or `.envrc`.
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+    /// Installed GCC toolchains.
+    #[serde(default)]
+    pub gcc: Vec<GccReceipt>,
+    /// Shell profiles modified to load the export file.
+    #[serde(default)]
+    pub profiles: Vec<PathBuf>,
+    /// Installed Xtensa Rust toolchain version.
+    #[serde(default)]
+    pub xtensa_rust: Option<String>,
+}
+
+impl Receipt {
+    /// Gets the path of the receipt for the given toolchain name.
+    fn get_path(toolchain_name: &str) -> PathBuf {
+        BaseDirs::new()
+            .unwrap()
+            .data_dir()
+            .join("espup")
+            .join(format!("{toolchain_name}.json"))
+    }
+
+    /// Loads the receipt of the given toolchain name, returns an empty receipt if there is none.
+    pub fn load(toolchain_name: &str) -> Result<Self, Error> {
+        let path = Self::get_path(toolchain_name);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        debug!("Loading install receipt from '{}'", path.display());
+        serde_json::from_str(&read_to_string(&path)?)
+            .map_err(|_| Error::InvalidReceipt(path.display().to_string()))
+    }
+
+    /// Saves the receipt of the given toolchain name.
+    pub fn save(&self, toolchain_name: &str) -> Result<(), Error> {
+        let path = Self::get_path(toolchain_name);
+        let parent = path.parent().unwrap();
+        if !parent.exists() {
+            create_dir_all(parent)
+                .map_err(|_| Error::CreateDirectory(parent.display().to_string()))?;
+        }
+        debug!("Saving install receipt to '{}'", path.display());
+        write(&path, serde_json::to_string_pretty(self).unwrap())?;
+        Ok(())
+    }
+
+    /// Removes the receipt of the given toolchain name.
+    pub fn remove(toolchain_name: &str) -> Result<(), Error> {
+        let path = Self::get_path(toolchain_name);
+        if path.exists() {
+            remove_file(&path)?;
+        }
+        Ok(())
+    }
+
+    /// Records a GCC toolchain, replacing any previous record of the same architecture.
+    pub fn add_gcc(&mut self, gcc: GccReceipt) {
+        self.gcc.retain(|g| g.arch != gcc.arch);
+        self.gcc.push(gcc);
+    }
+
+    /// Records a written dotenv file.
+    pub fn add_dotenv_file(&mut self, dotenv_file: PathBuf) {
+        add_path(&mut self.dotenv_files, dotenv_file);
+    }
+
+    /// Records a file with a block managed by espup.
+    pub fn add_file(&mut self, file: PathBuf) {
+        add_path(&mut self.files, file);
+    }
+
+    /// Records a modified shell profile.
+    pub fn add_profile(&mut self, profile: PathBuf) {
+        add_path(&mut self.profiles, profile);
+    }
+}
+
+/// Adds a path to the list, unless it is already recorded.
+fn add_path(paths: &mut Vec<PathBuf>, path: PathBuf) {
+    if !paths.contains(&path) {
+        paths.push(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::receipt::{GccReceipt, Receipt};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_receipt_add_gcc() {
+        let mut receipt = Receipt::default();
+        receipt.add_gcc(GccReceipt {
+            arch: "xtensa-esp-elf".to_string(),
+            path: PathBuf::from("/old"),
+            bin_path: "/old/xtensa-esp-elf/bin".to_string(),
+        });
+        receipt.add_gcc(GccReceipt {
+            arch: "riscv32-esp-elf".to_string(),
+            path: PathBuf::from("/riscv"),
+            bin_path: "/riscv/riscv32-esp-elf/bin".to_string(),
+        });
+        receipt.add_gcc(GccReceipt {
+            arch: "xtensa-esp-elf".to_string(),
+            path: PathBuf::from("/new"),
+            bin_path: "/new/xtensa-esp-elf/bin".to_string(),
+        });
+        assert_eq!(receipt.gcc.len(), 2);
+        assert_eq!(receipt.gcc[1].path, PathBuf::from("/new"));
+
+        // Round trip, missing fields use their default value
+        let json = serde_json::to_string(&receipt).unwrap();
+        assert_eq!(serde_json::from_str::<Receipt>(&json).unwrap(), receipt);
+        assert_eq!(
+            serde_json::from_str::<Receipt>("{}").unwrap(),
+            Receipt::default()
+        );
+    }
+
+    #[test]
+    fn test_receipt_add_file() {
+        let mut receipt = Receipt::default();
+        receipt.add_file(PathBuf::from("/home/esp/export-esp.sh"));
+        receipt.add_file(PathBuf::from("/project/.envrc"));
+        receipt.add_file(PathBuf::from("/home/esp/export-esp.sh"));
+        receipt.add_dotenv_file(PathBuf::from("/project/.env"));
+        assert_eq!(
+            receipt.files,
+            vec![
+                PathBuf::from("/home/esp/export-esp.sh"),
+                PathBuf::from("/project/.envrc")
+            ]
+        );
+        assert_eq!(receipt.dotenv_files, vec![PathBuf::from("/project/.env")]);
+    }
+}
@@ -1,10 +1,10 @@
 //! ESP32 chip variants support.
 
 use crate::error::Error;
-use log::debug;
 use miette::Result;
 use std::{collections::HashSet, str::FromStr};
 use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
+use tracing::debug;
 
 #[derive(Clone, Copy, EnumIter, EnumString, PartialEq, Hash, Eq, Debug, Display)]
 #[strum(serialize_all = "lowercase")]
@@ -0,0 +1,218 @@
+//! CMake and Ninja source and installation tools.
+
+use crate::{
+    env::Export,
+    error::Error,
+    host_triple::HostTriple,
+    toolchain::{download_file, get_checksum, Installable},
+};
+use async_trait::async_trait;
+use miette::Result;
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::{
+    fs::{set_permissions, Permissions},
+    os::unix::fs::PermissionsExt,
+};
+use tracing::{debug, info, warn};
+
+const CMAKE_REPOSITORY: &str = "https://github.com/Kitware/CMake/releases/download";
+pub const CMAKE_VERSION: &str = "3.24.0";
+const NINJA_REPOSITORY: &str = "https://github.com/ninja-build/ninja/releases/download";
+pub const NINJA_VERSION: &str = "1.12.1";
+/// Directory, inside the toolchain directory, where the build tools are installed.
+const BUILD_TOOLS_DIR: &str = "build-tools";
+
+#[derive(Debug, Clone)]
+pub struct Cmake {
+    /// Host triple.
+    pub host_triple: HostTriple,
+    /// CMake path.
+    pub path: PathBuf,
+}
+
+impl Cmake {
+    /// Gets the name of the CMake release artifact, without extension.
+    fn get_artifact_name(&self) -> String {
+        let arch = match self.host_triple {
+            HostTriple::X86_64AppleDarwin | HostTriple::Aarch64AppleDarwin => "macos-universal",
+            HostTriple::X86_64UnknownLinuxGnu => "linux-x86_64",
+            HostTriple::Aarch64UnknownLinuxGnu => "linux-aarch64",
+            HostTriple::X86_64PcWindowsMsvc | HostTriple::X86_64PcWindowsGnu => "windows-x86_64",
+        };
+        format!("cmake-{CMAKE_VERSION}-{arch}")
+    }
+
+    /// Gets the binary path.
+    pub fn get_bin_path(&self) -> String {
+        let bin_path = match self.host_triple {
+            HostTriple::X86_64AppleDarwin | HostTriple::Aarch64AppleDarwin => format!(
+                "{}/{}/CMake.app/Contents/bin",
+                self.path.to_str().unwrap(),
+                self.get_artifact_name()
+            ),
+            _ => format!(
+                "{}/{}/bin",
+                self.path.to_str().unwrap(),
+                self.get_artifact_name()
+            ),
+        };
+        match std::cfg!(windows) {
+            true => bin_path.replace('/', "\\"),
+            false => bin_path,
+        }
+    }
+
+    /// Create a new instance with default values and proper toolchain name.
+    pub fn new(host_triple: &HostTriple, toolchain_path: &Path) -> Self {
+        Self {
+            host_triple: host_triple.clone(),
+            path: toolchain_path
+                .join(BUILD_TOOLS_DIR)
+                .join(format!("cmake-{CMAKE_VERSION}")),
+        }
+    }
+}
+
+#[async_trait]
+impl Installable for Cmake {
+    async fn install(&self) -> Result<Vec<Export>, Error> {
+        info!("Installing CMake");
+        debug!("CMake path: {}", self.path.display());
+
+        if self.path.exists() {
+            warn!(
+                "Previous installation of CMake exists in: '{}'. Reusing this installation",
+                &self.path.display()
+            );
+        } else {
+            let extension = match self.host_triple {
+                HostTriple::X86_64PcWindowsMsvc | HostTriple::X86_64PcWindowsGnu => "zip",
+                _ => "tar.gz",
+            };
+            let cmake_file = format!("{}.{}", self.get_artifact_name(), extension);
+            let checksum_url =
+                format!("{CMAKE_REPOSITORY}/v{CMAKE_VERSION}/cmake-{CMAKE_VERSION}-SHA-256.txt");
+            let checksum = get_checksum(&checksum_url, &cmake_file).await;
+            download_file(
+                format!("{CMAKE_REPOSITORY}/v{CMAKE_VERSION}/{cmake_file}"),
+                &format!("cmake.{extension}"),
+                &self.path.display().to_string(),
+                true,
+                false,
+                checksum,
+            )
+            .await?;
+        }
+
+        Ok(vec![Export::Path(self.get_bin_path())])
+    }
+
+    fn name(&self) -> String {
+        "CMake".to_string()
+    }
+
+    fn path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+
+    fn version(&self) -> String {
+        CMAKE_VERSION.to_string()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Ninja {
+    /// Host triple.
+    pub host_triple: HostTriple,
+    /// Ninja path.
+    pub path: PathBuf,
+}
+
+impl Ninja {
+    /// Gets the binary path.
+    pub fn get_bin_path(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    /// Create a new instance with default values and proper toolchain name.
+    pub fn new(host_triple: &HostTriple, toolchain_path: &Path) -> Self {
+        Self {
+            host_triple: host_triple.clone(),
+            path: toolchain_path
+                .join(BUILD_TOOLS_DIR)
+                .join(format!("ninja-{NINJA_VERSION}")),
+        }
+    }
+}
+
+#[async_trait]
+impl Installable for Ninja {
+    async fn install(&self) -> Result<Vec<Export>, Error> {
+        info!("Installing Ninja");
+        debug!("Ninja path: {}", self.path.display());
+
+        if self.path.exists() {
+            warn!(
+                "Previous installation of Ninja exists in: '{}'. Reusing this installation",
+                &self.path.display()
+            );
+        } else {
+            let arch = match self.host_triple {
+                HostTriple::X86_64AppleDarwin | HostTriple::Aarch64AppleDarwin => "mac",
+                HostTriple::X86_64UnknownLinuxGnu => "linux",
+                HostTriple::Aarch64UnknownLinuxGnu => "linux-aarch64",
+                HostTriple::X86_64PcWindowsMsvc | HostTriple::X86_64PcWindowsGnu => "win",
+            };
+            // Ninja does not publish checksums for its releases
+            download_file(
+                format!("{NINJA_REPOSITORY}/v{NINJA_VERSION}/ninja-{arch}.zip"),
+                "ninja.zip",
+                &self.path.display().to_string(),
+                true,
+                false,
+                None,
+            )
+            .await?;
+            #[cfg(unix)]
+            set_permissions(self.path.join("ninja"), Permissions::from_mode(0o755))?;
+        }
+
+        Ok(vec![Export::Path(self.get_bin_path())])
+    }
+
+    fn name(&self) -> String {
+        "Ninja".to_string()
+    }
+
+    fn path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+
+    fn version(&self) -> String {
+        NINJA_VERSION.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{host_triple::HostTriple, toolchain::build_tools::Cmake};
+    use std::path::Path;
+
+    #[test]
+    fn test_cmake_bin_path() {
+        let toolchain_path = Path::new("/esp");
+        assert_eq!(
+            Cmake::new(&HostTriple::X86_64UnknownLinuxGnu, toolchain_path)
+                .get_bin_path()
+                .replace('\\', "/"),
+            "/esp/build-tools/cmake-3.24.0/cmake-3.24.0-linux-x86_64/bin"
+        );
+        assert_eq!(
+            Cmake::new(&HostTriple::Aarch64AppleDarwin, toolchain_path)
+                .get_bin_path()
+                .replace('\\', "/"),
+            "/esp/build-tools/cmake-3.24.0/cmake-3.24.0-macos-universal/CMake.app/Contents/bin"
+        );
+    }
+}
@@ -1,23 +1,65 @@
 //! GCC Toolchain source and installation tools.
 
 use crate::{
+    env::Export,
     error::Error,
     host_triple::HostTriple,
-    toolchain::{download_file, Installable},
+    receipt::{GccReceipt, Receipt},
+    targets::Target,
+    toolchain::{download_file, get_checksum, Installable},
 };
 use async_trait::async_trait;
-use log::{debug, info, warn};
 use miette::Result;
-use std::path::{Path, PathBuf};
+use regex::Regex;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
 #[cfg(windows)]
 use std::{env, fs::File};
-use tokio::fs::remove_dir_all;
+use tokio::fs::{remove_dir, remove_dir_all};
+use tracing::{debug, info, warn};
 
 const DEFAULT_GCC_REPOSITORY: &str = "https://github.com/espressif/crosstool-NG/releases/download";
-const DEFAULT_GCC_RELEASE: &str = "13.2.0_20230928";
+pub const DEFAULT_GCC_RELEASE: &str = "13.2.0_20230928";
+/// GCC release regex, '<major>.<minor>.<patch>_<date>'.
+const RE_GCC_RELEASE: &str = r"^(0|[1-9]\d*)\.(0|[1-9]\d*)\.(0|[1-9]\d*)_(\d{8})$";
+/// Legacy GCC release regex, '<year>r<revision>[-patch<patch>]'.
+const RE_LEGACY_GCC_RELEASE: &str = r"^20\d{2}r\d+(-patch\d+)?$";
+/// First GCC release shipping the unified Xtensa toolchain, as major, minor, patch and date.
+const UNIFIED_GCC_RELEASE: [u64; 4] = [13, 2, 0, 20230928];
+/// Per-chip Xtensa GCC toolchains, shipped by the releases before 13.2.0_20230928.
+pub const LEGACY_XTENSA_GCC: [&str; 3] = [
+    "xtensa-esp32-elf",
+    "xtensa-esp32s2-elf",
+    "xtensa-esp32s3-elf",
+];
 pub const RISCV_GCC: &str = "riscv32-esp-elf";
 pub const XTENSA_GCC: &str = "xtensa-esp-elf";
 
+/// Layout of the GCC release artifacts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GccLayout {
+    /// Single Xtensa toolchain for every chip, `xtensa-esp-elf`.
+    Unified,
+    /// One Xtensa toolchain per chip, `xtensa-<chip>-elf`.
+    PerChip,
+}
+
+impl GccLayout {
+    /// Gets the layout used by the given GCC release.
+    pub fn from_version(version: &str) -> Self {
+        let release = Regex::new(RE_GCC_RELEASE)
+            .unwrap()
+            .captures(version)
+            .map(|captures| [1, 2, 3, 4].map(|i| captures[i].parse::<u64>().unwrap()));
+        match release {
+            Some(release) if release >= UNIFIED_GCC_RELEASE => GccLayout::Unified,
+            _ => GccLayout::PerChip,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Gcc {
     /// Host triple.
@@ -26,6 +68,8 @@ pub struct Gcc {
     pub arch: String,
     /// GCC Toolchain path.
     pub path: PathBuf,
+    /// GCC release.
+    pub version: String,
 }
 
 impl Gcc {
@@ -38,12 +82,28 @@ impl Gcc {
         }
     }
 
+    /// Gets the directory where the toolchain is installed.
+    fn get_install_path(&self) -> PathBuf {
+        #[cfg(unix)]
+        let install_path = self.path.clone();
+        #[cfg(windows)]
+        let install_path = self.path.join(&self.arch);
+        install_path
+    }
+
+    /// Gets the receipt recording this toolchain installation.
+    pub fn receipt(&self) -> GccReceipt {
+        GccReceipt {
+            arch: self.arch.clone(),
+            path: self.get_install_path(),
+            bin_path: self.get_bin_path(),
+        }
+    }
+
     /// Create a new instance with default values and proper toolchain name.
-    pub fn new(arch: &str, host_triple: &HostTriple, toolchain_path: &Path) -> Self {
+    pub fn new(arch: &str, host_triple: &HostTriple, toolchain_path: &Path, version: &str) -> Self {
         #[cfg(unix)]
-        let path = toolchain_path
-            .join(arch)
-            .join(format!("esp-{DEFAULT_GCC_RELEASE}"));
+        let path = toolchain_path.join(arch).join(format!("esp-{version}"));
         #[cfg(windows)]
         let path: PathBuf = toolchain_path.into();
 
@@ -51,25 +111,60 @@ impl Gcc {
             host_triple: host_triple.clone(),
             arch: arch.to_string(),
             path,
+            version: version.to_string(),
         }
     }
+
+    /// Gets the name of the release artifact.
+    fn get_artifact_name(&self) -> String {
+        let extension = get_artifact_extension(&self.host_triple, &self.version);
+        if Regex::new(RE_LEGACY_GCC_RELEASE)
+            .unwrap()
+            .is_match(&self.version)
+        {
+            format!(
+                "{}-gcc{}-esp-{}-{}.{}",
+                self.arch,
+                get_legacy_gcc_version(&self.version),
+                self.version,
+                get_legacy_arch(&self.host_triple),
+                extension
+            )
+        } else {
+            format!(
+                "{}-{}-{}.{}",
+                self.arch,
+                self.version,
+                get_arch(&self.host_triple).unwrap(),
+                extension
+            )
+        }
+    }
+
+    /// Parses the GCC release, '<major>.<minor>.<patch>_<date>' or the legacy '<year>r<revision>'.
+    pub fn parse_version(version: &str) -> Result<String, Error> {
+        debug!("Parsing GCC version: {}", version);
+        let version = version.trim_start_matches("esp-");
+        if !Regex::new(RE_GCC_RELEASE).unwrap().is_match(version)
+            && !Regex::new(RE_LEGACY_GCC_RELEASE).unwrap().is_match(version)
+        {
+            return Err(Error::InvalidGccVersion(version.to_string()));
+        }
+        Ok(version.to_string())
+    }
 }
 
 #[async_trait]
 impl Installable for Gcc {
-    async fn install(&self) -> Result<Vec<String>, Error> {
-        let extension = get_artifact_extension(&self.host_triple);
+    async fn install(&self) -> Result<Vec<Export>, Error> {
+        let extension = get_artifact_extension(&self.host_triple, &self.version);
         info!("Installing GCC ({})", self.arch);
         debug!("GCC path: {}", self.path.display());
 
         #[cfg(unix)]
         let is_installed = self.path.exists();
         #[cfg(windows)]
-        let is_installed = self
-            .path
-            .join(&self.arch)
-            .join(DEFAULT_GCC_RELEASE)
-            .exists();
+        let is_installed = self.path.join(&self.arch).join(&self.version).exists();
 
         if is_installed {
             warn!(
@@ -77,51 +172,55 @@ impl Installable for Gcc {
                 &self.path.display()
             );
         } else {
-            let gcc_file = format!(
-                "{}-{}-{}.{}",
-                self.arch,
-                DEFAULT_GCC_RELEASE,
-                get_arch(&self.host_triple).unwrap(),
-                extension
-            );
+            // Windows installations are not versioned, remove any other release first
+            #[cfg(windows)]
+            if self.path.join(&self.arch).exists() {
+                remove_dir_all(self.path.join(&self.arch))
+                    .await
+                    .map_err(|_| {
+                        Error::RemoveDirectory(self.path.join(&self.arch).display().to_string())
+                    })?;
+            }
+            let gcc_file = self.get_artifact_name();
             let gcc_dist_url = format!(
                 "{}/esp-{}/{}",
-                DEFAULT_GCC_REPOSITORY, DEFAULT_GCC_RELEASE, gcc_file
+                DEFAULT_GCC_REPOSITORY, self.version, gcc_file
+            );
+            let checksum_url = format!(
+                "{}/esp-{}/crosstool-NG-esp-{}-checksum.sha256",
+                DEFAULT_GCC_REPOSITORY, self.version, self.version
             );
+            let checksum = get_checksum(&checksum_url, &gcc_file).await;
             download_file(
                 gcc_dist_url,
                 &format!("{}.{}", &self.arch, extension),
                 &self.path.display().to_string(),
                 true,
                 false,
+                checksum,
             )
             .await?;
         }
-        let mut exports: Vec<String> = Vec::new();
+        let exports = vec![Export::Path(self.get_bin_path())];
 
         #[cfg(windows)]
         if cfg!(windows) {
-            File::create(self.path.join(&self.arch).join(DEFAULT_GCC_RELEASE))?;
-
-            exports.push(format!(
-                "$Env:PATH = \"{};\" + $Env:PATH",
-                &self.get_bin_path()
-            ));
-            if self.arch == RISCV_GCC {
-                env::set_var("RISCV_GCC", self.get_bin_path());
-            } else {
-                env::set_var("XTENSA_GCC", self.get_bin_path());
-            }
+            File::create(self.path.join(&self.arch).join(&self.version))?;
         }
-        #[cfg(unix)]
-        exports.push(format!("export PATH=\"{}:$PATH\"", &self.get_bin_path()));
-
         Ok(exports)
     }
 
     fn name(&self) -> String {
         format!("GCC ({})", self.arch)
     }
+
+    fn path(&self) -> Option<PathBuf> {
+        Some(self.get_install_path())
+    }
+
+    fn version(&self) -> String {
+        self.version.clone()
+    }
 }
 
 /// Gets the name of the GCC arch based on the host triple.
@@ -137,38 +236,86 @@ fn get_arch(host_triple: &HostTriple) -> Result<&str> {
     }
 }
 
-/// Gets the artifact extension based on the host triple.
-fn get_artifact_extension(host_triple: &HostTriple) -> &str {
+/// Gets the name of the GCC arch used by legacy '<year>r<revision>' releases based on the host triple.
+fn get_legacy_arch(host_triple: &HostTriple) -> &str {
+    match host_triple {
+        HostTriple::X86_64AppleDarwin => "macos",
+        HostTriple::Aarch64AppleDarwin => "macos-arm64",
+        HostTriple::X86_64UnknownLinuxGnu => "linux-amd64",
+        HostTriple::Aarch64UnknownLinuxGnu => "linux-arm64",
+        HostTriple::X86_64PcWindowsMsvc | HostTriple::X86_64PcWindowsGnu => "win64",
+    }
+}
+
+/// Gets the GCC version bundled in a legacy '<year>r<revision>' release, as used in the artifact names.
+fn get_legacy_gcc_version(version: &str) -> &str {
+    if version.starts_with("2021") {
+        "8_4_0"
+    } else {
+        "11_2_0"
+    }
+}
+
+/// Gets the artifact extension based on the host triple and GCC release.
+fn get_artifact_extension<'a>(host_triple: &HostTriple, version: &str) -> &'a str {
     match host_triple {
         HostTriple::X86_64PcWindowsMsvc | HostTriple::X86_64PcWindowsGnu => "zip",
+        _ if version.starts_with("2021") => "tar.gz",
         _ => "tar.xz",
     }
 }
 
+/// Gets the Xtensa GCC toolchains required for the given targets and GCC release.
+pub fn get_xtensa_gcc_arches(version: &str, targets: &HashSet<Target>) -> Vec<&'static str> {
+    match GccLayout::from_version(version) {
+        GccLayout::Unified => {
+            if targets.iter().any(|t| t.is_xtensa()) {
+                vec![XTENSA_GCC]
+            } else {
+                Vec::new()
+            }
+        }
+        GccLayout::PerChip => [Target::ESP32, Target::ESP32S2, Target::ESP32S3]
+            .iter()
+            .zip(LEGACY_XTENSA_GCC)
+            .filter(|(target, _)| targets.contains(target))
+            .map(|(_, arch)| arch)
+            .collect(),
+    }
+}
+
 /// Checks if the toolchain is pressent, if present uninstalls it.
-pub async fn uninstall_gcc_toolchains(toolchain_path: &Path) -> Result<(), Error> {
+///
+/// Removes every toolchain recorded in the receipt, as well as the ones in the default location.
+pub async fn uninstall_gcc_toolchains(
+    toolchain_path: &Path,
+    receipt: &Receipt,
+) -> Result<(), Error> {
     info!("Uninstalling GCC");
 
-    let gcc_toolchains = vec![XTENSA_GCC, RISCV_GCC];
+    for gcc in &receipt.gcc {
+        #[cfg(windows)]
+        remove_path_entry(&gcc.bin_path);
+        if gcc.path.exists() {
+            debug!("Removing GCC ({}) from '{}'", gcc.arch, gcc.path.display());
+            remove_dir_all(&gcc.path)
+                .await
+                .map_err(|_| Error::RemoveDirectory(gcc.path.display().to_string()))?;
+        }
+        // Only succeeds if the architecture directory is empty
+        if let Some(parent) = gcc.path.parent() {
+            let _ = remove_dir(parent).await;
+        }
+    }
+
+    let mut gcc_toolchains = vec![XTENSA_GCC, RISCV_GCC];
+    gcc_toolchains.extend(LEGACY_XTENSA_GCC);
 
     for toolchain in gcc_toolchains {
         let gcc_path = toolchain_path.join(toolchain);
         if gcc_path.exists() {
             #[cfg(windows)]
-            if cfg!(windows) {
-                let gcc_path = format!(
-                    "{}\\esp-{}\\{}\\bin",
-                    gcc_path.display(),
-                    DEFAULT_GCC_RELEASE,
-                    toolchain
-                );
-                env::set_var(
-                    "PATH",
-                    env::var("PATH")
-                        .unwrap()
-                        .replace(&format!("{gcc_path};"), ""),
-                );
-            }
+            remove_path_entry(&format!("{}\\bin", gcc_path.display()));
             remove_dir_all(&gcc_path)
                 .await
                 .map_err(|_| Error::RemoveDirectory(gcc_path.display().to_string()))?;
@@ -177,3 +324,91 @@ pub async fn uninstall_gcc_toolchains(toolchain_path: &Path) -> Result<(), Error
 
     Ok(())
 }
+
+/// Removes a directory from the `PATH` of the current process.
+#[cfg(windows)]
+pub(crate) fn remove_path_entry(bin_path: &str) {
+    env::set_var(
+        "PATH",
+        env::var("PATH")
+            .unwrap()
+            .replace(&format!("{};", bin_path.replace('/', "\\")), ""),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        host_triple::HostTriple,
+        targets::Target,
+        toolchain::gcc::{get_xtensa_gcc_arches, Gcc, GccLayout, XTENSA_GCC},
+    };
+    use std::{collections::HashSet, path::Path};
+
+    #[test]
+    fn test_gcc_parse_version() {
+        assert_eq!(
+            Gcc::parse_version("13.2.0_20230928").unwrap(),
+            "13.2.0_20230928"
+        );
+        assert_eq!(
+            Gcc::parse_version("esp-12.2.0_20230208").unwrap(),
+            "12.2.0_20230208"
+        );
+        assert!(Gcc::parse_version("13.2.0").is_err());
+        assert!(Gcc::parse_version("13.2_20230928").is_err());
+        assert!(Gcc::parse_version("a.2.0_20230928").is_err());
+        assert_eq!(Gcc::parse_version("esp-2022r1").unwrap(), "2022r1");
+        assert_eq!(
+            Gcc::parse_version("2021r2-patch5").unwrap(),
+            "2021r2-patch5"
+        );
+        assert!(Gcc::parse_version("2022").is_err());
+    }
+
+    #[test]
+    fn test_gcc_layout() {
+        assert_eq!(
+            GccLayout::from_version("13.2.0_20230928"),
+            GccLayout::Unified
+        );
+        assert_eq!(
+            GccLayout::from_version("12.2.0_20230208"),
+            GccLayout::PerChip
+        );
+        assert_eq!(GccLayout::from_version("2022r1"), GccLayout::PerChip);
+
+        let targets = HashSet::from([Target::ESP32, Target::ESP32S3, Target::ESP32C3]);
+        assert_eq!(
+            get_xtensa_gcc_arches("13.2.0_20230928", &targets),
+            vec!["xtensa-esp-elf"]
+        );
+        assert_eq!(
+            get_xtensa_gcc_arches("12.2.0_20230208", &targets),
+            vec!["xtensa-esp32-elf", "xtensa-esp32s3-elf"]
+        );
+        assert_eq!(
+            get_xtensa_gcc_arches("2022r1", &targets),
+            vec!["xtensa-esp32-elf", "xtensa-esp32s3-elf"]
+        );
+        assert!(get_xtensa_gcc_arches("2022r1", &HashSet::from([Target::ESP32C3])).is_empty());
+    }
+
+    #[test]
+    fn test_gcc_artifact_name() {
+        let host_triple = HostTriple::X86_64UnknownLinuxGnu;
+        let path = Path::new("/toolchains/esp");
+        assert_eq!(
+            Gcc::new(XTENSA_GCC, &host_triple, path, "13.2.0_20230928").get_artifact_name(),
+            "xtensa-esp-elf-13.2.0_20230928-x86_64-linux-gnu.tar.xz"
+        );
+        assert_eq!(
+            Gcc::new("xtensa-esp32-elf", &host_triple, path, "12.2.0_20230208").get_artifact_name(),
+            "xtensa-esp32-elf-12.2.0_20230208-x86_64-linux-gnu.tar.xz"
+        );
+        assert_eq!(
+            Gcc::new("xtensa-esp32-elf", &host_triple, path, "2022r1").get_artifact_name(),
+            "xtensa-esp32-elf-gcc11_2_0-esp-2022r1-linux-amd64.tar.xz"
+        );
+    }
+}
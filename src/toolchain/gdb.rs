@@ -0,0 +1,188 @@
+//! GDB source and installation tools.
+
+#[cfg(windows)]
+use crate::toolchain::gcc::remove_path_entry;
+use crate::{
+    env::Export,
+    error::Error,
+    host_triple::HostTriple,
+    targets::Target,
+    toolchain::{download_file, get_checksum, Installable},
+};
+use async_trait::async_trait;
+use miette::Result;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use tokio::fs::remove_dir_all;
+use tracing::{debug, info, warn};
+
+const DEFAULT_GDB_REPOSITORY: &str = "https://github.com/espressif/binutils-gdb/releases/download";
+pub const DEFAULT_GDB_RELEASE: &str = "12.1_20231023";
+pub const RISCV_GDB: &str = "riscv32-esp-elf-gdb";
+pub const XTENSA_GDB: &str = "xtensa-esp-elf-gdb";
+
+#[derive(Debug, Clone)]
+pub struct Gdb {
+    /// Host triple.
+    pub host_triple: HostTriple,
+    /// GDB architecture.
+    pub arch: String,
+    /// GDB path.
+    pub path: PathBuf,
+    /// GDB release.
+    pub version: String,
+}
+
+impl Gdb {
+    /// Gets the binary path.
+    pub fn get_bin_path(&self) -> String {
+        let bin_path = format!("{}/{}/bin", &self.path.to_str().unwrap(), &self.arch);
+        match std::cfg!(windows) {
+            true => bin_path.replace('/', "\\"),
+            false => bin_path,
+        }
+    }
+
+    /// Create a new instance with default values and proper toolchain name.
+    pub fn new(arch: &str, host_triple: &HostTriple, toolchain_path: &Path) -> Self {
+        let path = toolchain_path.join(arch).join(DEFAULT_GDB_RELEASE);
+
+        Self {
+            host_triple: host_triple.clone(),
+            arch: arch.to_string(),
+            path,
+            version: DEFAULT_GDB_RELEASE.to_string(),
+        }
+    }
+
+    /// Removes the GDB installations.
+    pub async fn uninstall(toolchain_path: &Path) -> Result<(), Error> {
+        for arch in [XTENSA_GDB, RISCV_GDB] {
+            let gdb_path = toolchain_path.join(arch);
+            if gdb_path.exists() {
+                info!("Uninstalling GDB ({})", arch);
+                #[cfg(windows)]
+                for entry in std::fs::read_dir(&gdb_path)?.flatten() {
+                    remove_path_entry(&format!("{}\\{}\\bin", entry.path().display(), arch));
+                }
+                remove_dir_all(&gdb_path)
+                    .await
+                    .map_err(|_| Error::RemoveDirectory(gdb_path.display().to_string()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Installable for Gdb {
+    async fn install(&self) -> Result<Vec<Export>, Error> {
+        let extension = get_artifact_extension(&self.host_triple);
+        info!("Installing GDB ({})", self.arch);
+        debug!("GDB path: {}", self.path.display());
+
+        if self.path.exists() {
+            warn!(
+                "Previous installation of GDB exists in: '{}'. Reusing this installation",
+                &self.path.display()
+            );
+        } else {
+            let gdb_file = format!(
+                "{}-{}-{}.{}",
+                self.arch,
+                self.version,
+                get_arch(&self.host_triple),
+                extension
+            );
+            let gdb_dist_url = format!(
+                "{}/esp-gdb-v{}/{}",
+                DEFAULT_GDB_REPOSITORY, self.version, gdb_file
+            );
+            let checksum_url = format!(
+                "{}/esp-gdb-v{}/esp-gdb-v{}-checksum.sha256",
+                DEFAULT_GDB_REPOSITORY, self.version, self.version
+            );
+            let checksum = get_checksum(&checksum_url, &gdb_file).await;
+            download_file(
+                gdb_dist_url,
+                &format!("{}.{}", &self.arch, extension),
+                &self.path.display().to_string(),
+                true,
+                false,
+                checksum,
+            )
+            .await?;
+        }
+        Ok(vec![Export::Path(self.get_bin_path())])
+    }
+
+    fn name(&self) -> String {
+        format!("GDB ({})", self.arch)
+    }
+
+    fn path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+
+    fn version(&self) -> String {
+        self.version.clone()
+    }
+}
+
+/// Gets the name of the GDB arch based on the host triple.
+fn get_arch(host_triple: &HostTriple) -> &str {
+    match host_triple {
+        HostTriple::X86_64AppleDarwin => "x86_64-apple-darwin21.1",
+        HostTriple::Aarch64AppleDarwin => "aarch64-apple-darwin21.1",
+        HostTriple::X86_64UnknownLinuxGnu => "x86_64-linux-gnu",
+        HostTriple::Aarch64UnknownLinuxGnu => "aarch64-linux-gnu",
+        HostTriple::X86_64PcWindowsMsvc | HostTriple::X86_64PcWindowsGnu => "x86_64-w64-mingw32",
+    }
+}
+
+/// Gets the artifact extension based on the host triple.
+fn get_artifact_extension(host_triple: &HostTriple) -> &str {
+    match host_triple {
+        HostTriple::X86_64PcWindowsMsvc | HostTriple::X86_64PcWindowsGnu => "zip",
+        _ => "tar.gz",
+    }
+}
+
+/// Gets the GDB packages required for the given targets.
+pub fn get_gdb_arches(targets: &HashSet<Target>) -> Vec<&'static str> {
+    let mut arches = Vec::new();
+    if targets.iter().any(|t| t.is_xtensa()) {
+        arches.push(XTENSA_GDB);
+    }
+    if targets.iter().any(|t| t.is_riscv()) {
+        arches.push(RISCV_GDB);
+    }
+    arches
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        targets::Target,
+        toolchain::gdb::{get_gdb_arches, RISCV_GDB, XTENSA_GDB},
+    };
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_get_gdb_arches() {
+        assert_eq!(
+            get_gdb_arches(&HashSet::from([Target::ESP32, Target::ESP32S2])),
+            vec![XTENSA_GDB]
+        );
+        assert_eq!(
+            get_gdb_arches(&HashSet::from([Target::ESP32C3])),
+            vec![RISCV_GDB]
+        );
+        assert_eq!(
+            get_gdb_arches(&HashSet::from([Target::ESP32S3, Target::ESP32C6])),
+            vec![XTENSA_GDB, RISCV_GDB]
+        );
+    }
+}
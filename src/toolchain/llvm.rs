@@ -1,29 +1,42 @@
 //! LLVM Toolchain source and installation tools.
 
 use crate::{
+    env::Export,
     error::Error,
     host_triple::HostTriple,
-    toolchain::{download_file, rust::RE_EXTENDED_SEMANTIC_VERSION, Installable},
+    toolchain::{
+        download_cached, get_cache_dir, get_checksum, github_query,
+        rust::RE_EXTENDED_SEMANTIC_VERSION, timings::add_extraction, write_file, Installable,
+    },
 };
 use async_trait::async_trait;
 #[cfg(unix)]
 use directories::BaseDirs;
-use log::{info, warn};
 use miette::Result;
 use regex::Regex;
-use std::path::{Path, PathBuf};
 #[cfg(windows)]
-use std::{env, fs::File};
+use std::{env, fs::read_dir};
 #[cfg(unix)]
 use std::{fs::create_dir_all, os::unix::fs::symlink};
+use std::{
+    path::{Path, PathBuf},
+    time::Instant,
+};
 use tokio::fs::remove_dir_all;
+#[cfg(unix)]
+use tokio::fs::{read_dir, remove_dir, remove_file};
+use tracing::{debug, info, warn};
 
 const DEFAULT_LLVM_REPOSITORY: &str = "https://github.com/espressif/llvm-project/releases/download";
+const LLVM_API_URL: &str = "https://api.github.com/repos/espressif/llvm-project/releases";
 const DEFAULT_LLVM_15_VERSION: &str = "esp-15.0.0-20221201";
-#[cfg(windows)]
-const OLD_LLVM_16_VERSION: &str = "esp-16.0.0-20230516";
 const DEFAULT_LLVM_16_VERSION: &str = "esp-16.0.4-20231113";
 pub const CLANG_NAME: &str = "xtensa-esp32-elf-clang";
+/// LLVM version regex.
+const RE_LLVM_VERSION: &str = r"^esp-(0|[1-9]\d*)\.(0|[1-9]\d*)\.(0|[1-9]\d*)[-_]\d{8}$";
+/// Tools directory used by older installations and documentation.
+#[cfg(unix)]
+const LEGACY_TOOLS_PATH: &str = ".espressif/tools";
 
 #[derive(Debug, Clone, Default)]
 pub struct Llvm {
@@ -33,6 +46,8 @@ pub struct Llvm {
     pub file_name: String,
     /// Host triple.
     pub host_triple: HostTriple,
+    /// If `true`, symlinks matching the legacy `xtensa-esp32-elf-clang` layout are created.
+    pub legacy_path: bool,
     /// LLVM Toolchain path.
     pub path: PathBuf,
     /// The repository containing LLVM sources.
@@ -70,11 +85,96 @@ impl Llvm {
         }
     }
 
+    /// Gets the path used by older installations, `~/.espressif/tools/xtensa-esp32-elf-clang/<version>-<host_triple>`.
+    #[cfg(unix)]
+    fn get_legacy_path(&self) -> PathBuf {
+        BaseDirs::new()
+            .unwrap()
+            .home_dir()
+            .join(LEGACY_TOOLS_PATH)
+            .join(CLANG_NAME)
+            .join(format!("{}-{}", self.version, self.host_triple))
+    }
+
+    /// Creates the legacy `xtensa-esp32-elf-clang` layout pointing to the current installation.
+    #[cfg(unix)]
+    async fn create_legacy_symlink(&self) -> Result<(), Error> {
+        let legacy_path = self.get_legacy_path();
+        if !legacy_path.exists() {
+            create_dir_all(&legacy_path)
+                .map_err(|_| Error::CreateDirectory(legacy_path.display().to_string()))?;
+        }
+        let legacy_symlink_path = legacy_path.join("esp-clang");
+        if legacy_symlink_path.is_symlink() {
+            remove_file(&legacy_symlink_path).await?;
+        } else if legacy_symlink_path.exists() {
+            warn!(
+                "Legacy LLVM path '{}' already exists and is not managed by espup, skipping symlink creation",
+                legacy_symlink_path.display()
+            );
+            return Ok(());
+        }
+        info!(
+            "Creating legacy symlink between '{}' and '{}'",
+            self.path.join("esp-clang").display(),
+            legacy_symlink_path.display()
+        );
+        symlink(self.path.join("esp-clang"), legacy_symlink_path)?;
+        Ok(())
+    }
+
+    /// Removes the legacy `xtensa-esp32-elf-clang` symlinks, leaving any non espup directories untouched.
+    #[cfg(unix)]
+    async fn remove_legacy_symlinks() -> Result<(), Error> {
+        let legacy_dir = BaseDirs::new()
+            .unwrap()
+            .home_dir()
+            .join(LEGACY_TOOLS_PATH)
+            .join(CLANG_NAME);
+        if !legacy_dir.exists() {
+            return Ok(());
+        }
+        let mut entries = read_dir(&legacy_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let legacy_symlink_path = entry.path().join("esp-clang");
+            if legacy_symlink_path.is_symlink() {
+                debug!(
+                    "Removing legacy LLVM symlink '{}'",
+                    legacy_symlink_path.display()
+                );
+                remove_file(&legacy_symlink_path).await?;
+                // Only succeeds if the directory is empty
+                let _ = remove_dir(entry.path()).await;
+            }
+        }
+        let _ = remove_dir(&legacy_dir).await;
+        Ok(())
+    }
+
+    /// Gets the version of the latest LLVM pre-release.
+    pub async fn get_nightly_version() -> Result<String, Error> {
+        let json = github_query(LLVM_API_URL).await?;
+        let version = json
+            .as_array()
+            .and_then(|releases| {
+                releases
+                    .iter()
+                    .find(|release| release["prerelease"].as_bool().unwrap_or(false))
+            })
+            .and_then(|release| release["tag_name"].as_str())
+            .ok_or(Error::MissingLlvmNightly)?
+            .to_string();
+        debug!("Latest LLVM pre-release: {}", version);
+        Ok(version)
+    }
+
     /// Create a new instance with default values and proper toolchain version.
     pub fn new(
         toolchain_path: &Path,
         host_triple: &HostTriple,
         extended: bool,
+        legacy_path: bool,
+        llvm_version: Option<&str>,
         xtensa_rust_version: &str,
     ) -> Result<Self, Error> {
         let re_extended: Regex = Regex::new(RE_EXTENDED_SEMANTIC_VERSION).unwrap();
@@ -89,7 +189,12 @@ impl Llvm {
         };
 
         // Use LLVM 15 for versions 1.69.0.0 and below
-        let version = if (major == 1 && minor == 69 && patch == 0 && subpatch == 0)
+        let version = if let Some(llvm_version) = llvm_version {
+            if !Regex::new(RE_LLVM_VERSION).unwrap().is_match(llvm_version) {
+                return Err(Error::InvalidLlvmVersion(llvm_version.to_string()));
+            }
+            llvm_version.to_string()
+        } else if (major == 1 && minor == 69 && patch == 0 && subpatch == 0)
             || (major == 1 && minor < 69)
         {
             DEFAULT_LLVM_15_VERSION.to_string()
@@ -106,15 +211,13 @@ impl Llvm {
             file_name = format!("libs_{file_name}");
         }
         let repository_url = format!("{DEFAULT_LLVM_REPOSITORY}/{version}/{file_name}");
-        #[cfg(unix)]
         let path = toolchain_path.join(CLANG_NAME).join(&version);
-        #[cfg(windows)]
-        let path = toolchain_path.join(CLANG_NAME);
 
         Ok(Self {
             extended,
             file_name,
             host_triple: host_triple.clone(),
+            legacy_path,
             path,
             repository_url,
             version,
@@ -130,37 +233,21 @@ impl Llvm {
             if cfg!(windows) {
                 env::remove_var("LIBCLANG_PATH");
                 env::remove_var("CLANG_PATH");
-                let mut updated_path = env::var("PATH").unwrap().replace(
-                    &format!(
-                        "{}\\{}\\esp-clang\\bin;",
-                        llvm_path.display().to_string().replace('/', "\\"),
-                        DEFAULT_LLVM_15_VERSION,
-                    ),
-                    "",
-                );
-                updated_path = updated_path.replace(
-                    &format!(
-                        "{}\\{}\\esp-clang\\bin;",
-                        llvm_path.display().to_string().replace('/', "\\"),
-                        OLD_LLVM_16_VERSION,
-                    ),
-                    "",
-                );
-                updated_path = updated_path.replace(
-                    &format!(
-                        "{}\\{}\\esp-clang\\bin;",
-                        llvm_path.display().to_string().replace('/', "\\"),
-                        DEFAULT_LLVM_16_VERSION,
-                    ),
-                    "",
-                );
-                updated_path = updated_path.replace(
-                    &format!(
-                        "{}\\esp-clang\\bin;",
-                        llvm_path.display().to_string().replace('/', "\\"),
-                    ),
-                    "",
-                );
+                let llvm_path_str = llvm_path.display().to_string().replace('/', "\\");
+                // Remove every side by side version, as well as the unversioned legacy layout
+                let mut updated_path = env::var("PATH")
+                    .unwrap()
+                    .replace(&format!("{llvm_path_str}\\esp-clang\\bin;"), "");
+                for entry in read_dir(&llvm_path)? {
+                    updated_path = updated_path.replace(
+                        &format!(
+                            "{}\\{}\\esp-clang\\bin;",
+                            llvm_path_str,
+                            entry?.file_name().to_string_lossy()
+                        ),
+                        "",
+                    );
+                }
                 env::set_var("PATH", updated_path);
             }
             remove_dir_all(&llvm_path)
@@ -175,6 +262,7 @@ impl Llvm {
                         .await
                         .map_err(|_| Error::RemoveDirectory(espup_dir.display().to_string()))?;
                 }
+                Self::remove_legacy_symlinks().await?;
             }
         }
         Ok(())
@@ -183,21 +271,14 @@ impl Llvm {
 
 #[async_trait]
 impl Installable for Llvm {
-    async fn install(&self) -> Result<Vec<String>, Error> {
-        let mut exports: Vec<String> = Vec::new();
+    async fn install(&self) -> Result<Vec<Export>, Error> {
+        let mut exports: Vec<Export> = Vec::new();
 
-        #[cfg(unix)]
         let install_path = if self.extended {
             Path::new(&self.path).join("esp-clang").join("include")
         } else {
             Path::new(&self.path).to_path_buf()
         };
-        #[cfg(windows)]
-        let install_path = if self.extended {
-            self.path.join(&self.version).join("include")
-        } else {
-            self.path.join(&self.version)
-        };
 
         if install_path.exists() {
             warn!(
@@ -206,31 +287,44 @@ impl Installable for Llvm {
             );
         } else {
             info!("Installing Xtensa LLVM");
-            download_file(
-                self.repository_url.clone(),
+            let checksum_url = format!(
+                "{DEFAULT_LLVM_REPOSITORY}/{}/{}-checksum.sha256",
+                self.version, self.version
+            );
+            let checksum = get_checksum(&checksum_url, &self.file_name).await;
+            // Artifacts are cached by version and host triple, so toolchains sharing the same
+            // LLVM version don't need to download it again
+            let cache_path = get_cache_dir()
+                .join(CLANG_NAME)
+                .join(&self.version)
+                .join(self.host_triple.to_string())
+                .join(&self.file_name);
+            let bytes =
+                download_cached(&self.repository_url, &cache_path, checksum.as_deref()).await?;
+            let start = Instant::now();
+            let res = write_file(
+                &bytes,
                 "idf_tool_xtensa_elf_clang.tar.xz",
                 self.path.to_str().unwrap(),
                 true,
                 false,
-            )
-            .await?;
+            );
+            add_extraction(start.elapsed());
+            res?;
         }
         // Set environment variables.
         #[cfg(windows)]
         if cfg!(windows) {
-            File::create(self.path.join(&self.version))?;
             let libclang_dll = format!("{}\\libclang.dll", self.get_lib_path());
-            exports.push(format!("$Env:LIBCLANG_PATH = \"{}\"", libclang_dll));
-            exports.push(format!(
-                "$Env:PATH = \"{};\" + $Env:PATH",
-                self.get_lib_path()
-            ));
-            env::set_var("LIBCLANG_BIN_PATH", self.get_lib_path());
-            env::set_var("LIBCLANG_PATH", libclang_dll);
+            exports.push(Export::Var("LIBCLANG_PATH".to_string(), libclang_dll));
+            exports.push(Export::Path(self.get_lib_path()));
         }
         #[cfg(unix)]
         if cfg!(unix) {
-            exports.push(format!("export LIBCLANG_PATH=\"{}\"", self.get_lib_path()));
+            exports.push(Export::Var(
+                "LIBCLANG_PATH".to_string(),
+                self.get_lib_path(),
+            ));
             let espup_dir = BaseDirs::new().unwrap().home_dir().join(".espup");
 
             if !espup_dir.exists() {
@@ -249,16 +343,18 @@ impl Installable for Llvm {
                 llvm_symlink_path.display()
             );
             symlink(self.get_lib_path(), llvm_symlink_path)?;
+
+            if self.legacy_path {
+                self.create_legacy_symlink().await?;
+            }
+        }
+        #[cfg(windows)]
+        if self.legacy_path {
+            warn!("Legacy LLVM paths are only supported on Unix systems, skipping them");
         }
 
         if self.extended {
-            #[cfg(windows)]
-            if cfg!(windows) {
-                exports.push(format!("$Env:CLANG_PATH = \"{}\"", self.get_bin_path()));
-                env::set_var("CLANG_PATH", self.get_bin_path());
-            }
-            #[cfg(unix)]
-            exports.push(format!("export CLANG_PATH=\"{}\"", self.get_bin_path()));
+            exports.push(Export::Var("CLANG_PATH".to_string(), self.get_bin_path()));
         }
 
         Ok(exports)
@@ -267,4 +363,64 @@ impl Installable for Llvm {
     fn name(&self) -> String {
         "LLVM".to_string()
     }
+
+    fn path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+
+    fn version(&self) -> String {
+        self.version.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{host_triple::HostTriple, toolchain::llvm::Llvm};
+    use std::path::Path;
+
+    #[tokio::test]
+    async fn test_get_nightly_version() {
+        // Querying GitHub from the async runtime must not panic, even without network access
+        if let Ok(version) = Llvm::get_nightly_version().await {
+            assert!(version.starts_with("esp-"));
+        }
+    }
+
+    #[test]
+    fn test_llvm_version() {
+        let toolchain_path = Path::new("/home/user/.rustup/toolchains/esp");
+        let host_triple = HostTriple::X86_64UnknownLinuxGnu;
+        // Version based on the Xtensa Rust version
+        let llvm = Llvm::new(toolchain_path, &host_triple, false, false, None, "1.69.0.0").unwrap();
+        assert_eq!(llvm.version, "esp-15.0.0-20221201");
+        let llvm = Llvm::new(toolchain_path, &host_triple, false, false, None, "1.74.0.0").unwrap();
+        assert_eq!(llvm.version, "esp-16.0.4-20231113");
+        // Selected version is installed side by side
+        let llvm = Llvm::new(
+            toolchain_path,
+            &host_triple,
+            false,
+            false,
+            Some("esp-16.0.0-20230516"),
+            "1.74.0.0",
+        )
+        .unwrap();
+        assert_eq!(llvm.version, "esp-16.0.0-20230516");
+        assert_eq!(
+            llvm.path,
+            toolchain_path
+                .join("xtensa-esp32-elf-clang")
+                .join("esp-16.0.0-20230516")
+        );
+        // Invalid selected version
+        assert!(Llvm::new(
+            toolchain_path,
+            &host_triple,
+            false,
+            false,
+            Some("16.0.0"),
+            "1.74.0.0"
+        )
+        .is_err());
+    }
 }
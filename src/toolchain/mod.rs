@@ -4,37 +4,64 @@
 use crate::env::set_env;
 use crate::{
     cli::InstallOpts,
-    env::{create_export_file, get_export_file, print_post_install_msg},
+    env::{
+        create_dotenv_file, create_export_file, format_exports, format_relocatable_exports,
+        get_export_file, install_profile, is_github_actions, is_stdout, print_post_install_msg,
+        render_template, update_envrc, update_github_env, warn_shadowed_tools, Export, Shell,
+    },
     error::Error,
+    events::{emit, is_json_events, Event},
     host_triple::get_host_triple,
+    interactive::confirm,
+    receipt::Receipt,
     targets::Target,
     toolchain::{
-        gcc::{Gcc, RISCV_GCC, XTENSA_GCC},
+        build_tools::{Cmake, Ninja},
+        gcc::{get_xtensa_gcc_arches, Gcc, RISCV_GCC},
+        gdb::{get_gdb_arches, Gdb},
         llvm::Llvm,
+        probe_rs::ProbeRs,
         rust::{check_rust_installation, get_rustup_home, RiscVTarget, XtensaRust},
+        timings::{
+            add_download, add_extraction, format_duration, format_timings_table, record, Timings,
+        },
     },
 };
 use async_trait::async_trait;
+use directories::BaseDirs;
 use flate2::bufread::GzDecoder;
-use log::{debug, info, warn};
 use miette::Result;
 use reqwest::{blocking::Client, header};
 use retry::{delay::Fixed, retry};
+use sha2::{Digest, Sha256};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
 use std::{
+    collections::HashMap,
     env,
-    fs::{create_dir_all, remove_file, File},
-    io::{copy, Write},
+    fs::{create_dir_all, read_to_string, remove_file, File},
+    io::{Read, Write},
     path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
 };
 use tar::Archive;
-use tokio::{fs::remove_dir_all, sync::mpsc};
+use tokio::{
+    fs::remove_dir_all,
+    sync::{mpsc, Semaphore},
+};
 use tokio_retry::{strategy::FixedInterval, Retry};
+use tracing::{debug, info, info_span, instrument, warn, Instrument, Span};
 use xz2::read::XzDecoder;
 use zip::ZipArchive;
 
+pub mod build_tools;
 pub mod gcc;
+pub mod gdb;
 pub mod llvm;
+pub mod probe_rs;
 pub mod rust;
+pub mod timings;
 
 pub enum InstallMode {
     Install,
@@ -44,18 +71,156 @@ pub enum InstallMode {
 #[async_trait]
 pub trait Installable {
     /// Install some application, returning a vector of any required exports
-    async fn install(&self) -> Result<Vec<String>, Error>;
+    async fn install(&self) -> Result<Vec<Export>, Error>;
     /// Returns the name of the toolchain being installeds
     fn name(&self) -> String;
+    /// Returns the directory the application is installed in, removed if its installation is interrupted
+    fn path(&self) -> Option<PathBuf> {
+        None
+    }
+    /// Returns the version being installed
+    fn version(&self) -> String;
+}
+
+/// Downloads a checksum file and returns the SHA-256 checksum listed for the given artifact.
+///
+/// Returns `None`, warning that the verification is skipped, if the checksum file can not be
+/// downloaded or does not list the artifact.
+pub async fn get_checksum(checksum_url: &str, artifact_name: &str) -> Option<String> {
+    let checksum = download_checksums(checksum_url)
+        .await
+        .and_then(|checksums| parse_checksum(&checksums, artifact_name));
+    if checksum.is_none() {
+        warn!(
+            "Checksum for '{}' is not available, skipping verification",
+            artifact_name
+        );
+    }
+    checksum
+}
+
+/// Downloads a checksum file.
+async fn download_checksums(checksum_url: &str) -> Option<String> {
+    debug!("Downloading checksums from '{}'", checksum_url);
+    match reqwest::get(checksum_url)
+        .await
+        .and_then(|resp| resp.error_for_status())
+    {
+        Ok(resp) => resp.text().await.ok(),
+        Err(e) => {
+            debug!("Failed to download checksums: {}", e);
+            None
+        }
+    }
+}
+
+/// Parses a `sha256sum` formatted file and returns the checksum of the given artifact.
+fn parse_checksum(checksums: &str, artifact_name: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let checksum = fields.next()?;
+        let file_name = fields.next()?.trim_start_matches('*');
+        (file_name == artifact_name).then(|| checksum.to_lowercase())
+    })
+}
+
+/// Verifies that the SHA-256 checksum of the bytes matches the expected one.
+pub fn verify_checksum(bytes: &[u8], expected: &str, file_name: &str) -> Result<(), Error> {
+    let checksum = format!("{:x}", Sha256::digest(bytes));
+    if checksum != expected {
+        return Err(Error::ChecksumMismatch(
+            file_name.to_string(),
+            expected.to_string(),
+            checksum,
+        ));
+    }
+    debug!("Checksum of '{}' verified", file_name);
+    Ok(())
 }
 
 /// Downloads a file from a URL and uncompresses it, if necesary, to the output directory.
+///
+/// If a checksum is provided, the SHA-256 of the downloaded file is verified before uncompressing it.
+#[instrument(name = "download", skip_all, fields(file = file_name))]
 pub async fn download_file(
     url: String,
     file_name: &str,
     output_directory: &str,
     uncompress: bool,
     strip: bool,
+    checksum: Option<String>,
+) -> Result<String, Error> {
+    info!("Downloading '{}'", &file_name);
+    let start = Instant::now();
+    let resp = reqwest::get(&url).await?.error_for_status()?;
+    let bytes = resp.bytes().await?;
+    add_download(start.elapsed());
+    if let Some(checksum) = checksum {
+        verify_checksum(&bytes, &checksum, file_name)?;
+    }
+    let start = Instant::now();
+    let res = write_file(&bytes, file_name, output_directory, uncompress, strip);
+    add_extraction(start.elapsed());
+    res
+}
+
+/// Downloads a file from a URL, reusing the copy stored in the cache path if it exists.
+///
+/// If a checksum is provided, both cached and downloaded files are verified.
+#[instrument(name = "download", skip_all, fields(file = %cache_path.display()))]
+pub async fn download_cached(
+    url: &str,
+    cache_path: &Path,
+    checksum: Option<&str>,
+) -> Result<Vec<u8>, Error> {
+    let file_name = cache_path.file_name().unwrap().to_str().unwrap();
+    let start = Instant::now();
+    if cache_path.exists() {
+        info!("Using cached '{}'", cache_path.display());
+        let bytes = tokio::fs::read(cache_path).await?;
+        match checksum.map(|checksum| verify_checksum(&bytes, checksum, file_name)) {
+            Some(Err(_)) => warn!(
+                "Cached '{}' is corrupted, downloading it again",
+                cache_path.display()
+            ),
+            _ => {
+                add_download(start.elapsed());
+                return Ok(bytes);
+            }
+        }
+    }
+
+    info!("Downloading '{}'", file_name);
+    debug!("Fetching '{}'", url);
+    let bytes = reqwest::get(url)
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?
+        .to_vec();
+    if let Some(checksum) = checksum {
+        verify_checksum(&bytes, checksum, file_name)?;
+    }
+    let cache_dir = cache_path.parent().unwrap();
+    create_dir_all(cache_dir)
+        .map_err(|_| Error::CreateDirectory(cache_dir.display().to_string()))?;
+    debug!("Caching '{}'", cache_path.display());
+    // Write to a temporary file first, so an interrupted write does not leave a truncated cache
+    let partial_path = cache_path.with_extension("part");
+    tokio::fs::write(&partial_path, &bytes).await?;
+    tokio::fs::rename(&partial_path, cache_path).await?;
+    add_download(start.elapsed());
+    Ok(bytes)
+}
+
+/// Writes the file contents to the output directory, uncompressing them if necessary.
+#[instrument(name = "extraction", skip_all, fields(file = file_name))]
+pub fn write_file(
+    bytes: &[u8],
+    file_name: &str,
+    output_directory: &str,
+    uncompress: bool,
+    strip: bool,
 ) -> Result<String, Error> {
     let file_path = format!("{output_directory}/{file_name}");
     if Path::new(&file_path).exists() {
@@ -69,53 +234,76 @@ pub async fn download_file(
         create_dir_all(output_directory)
             .map_err(|_| Error::CreateDirectory(output_directory.to_string()))?;
     }
-    info!("Downloading '{}'", &file_name);
-    let resp = reqwest::get(&url).await?;
-    let bytes = resp.bytes().await?;
     if uncompress {
         let extension = Path::new(file_name).extension().unwrap().to_str().unwrap();
         match extension {
             "zip" => {
+                debug!("Extracting zip file to '{}'", output_directory);
                 let mut tmpfile = tempfile::tempfile()?;
-                tmpfile.write_all(&bytes)?;
-                let mut zipfile = ZipArchive::new(tmpfile).unwrap();
-                if strip {
-                    for i in 0..zipfile.len() {
-                        let mut file = zipfile.by_index(i).unwrap();
-                        if !file.name().starts_with("esp/") {
-                            continue;
+                tmpfile.write_all(bytes)?;
+                let mut zipfile = ZipArchive::new(tmpfile)
+                    .map_err(|e| Error::ExtractFile(file_path.clone(), e.to_string()))?;
+                let output_directory = get_extended_length_path(Path::new(output_directory))?;
+                for i in 0..zipfile.len() {
+                    let mut file = zipfile
+                        .by_index(i)
+                        .map_err(|e| Error::ExtractFile(file_path.clone(), e.to_string()))?;
+                    // Skip entries that would be extracted outside of the output directory
+                    let Some(mut entry_path) = file.enclosed_name().map(Path::to_path_buf) else {
+                        continue;
+                    };
+                    if strip {
+                        match entry_path.strip_prefix("esp/") {
+                            Ok(stripped_path) => entry_path = stripped_path.to_path_buf(),
+                            Err(_) => continue,
                         }
+                    }
+                    let outpath = output_directory.join(entry_path);
+                    let extract_error = |e: std::io::Error| {
+                        Error::ExtractFile(outpath.display().to_string(), e.to_string())
+                    };
 
-                        let file_path = PathBuf::from(file.name().to_string());
-                        let stripped_name = file_path.strip_prefix("esp/").unwrap();
-                        let outpath = Path::new(output_directory).join(stripped_name);
-
-                        if file.name().ends_with('/') {
-                            create_dir_all(&outpath)?;
-                        } else {
-                            create_dir_all(outpath.parent().unwrap())?;
-                            let mut outfile = File::create(&outpath)?;
-                            copy(&mut file, &mut outfile)?;
+                    if file.is_dir() {
+                        create_dir_all(&outpath).map_err(extract_error)?;
+                        continue;
+                    }
+                    if let Some(parent) = outpath.parent() {
+                        create_dir_all(parent).map_err(extract_error)?;
+                    }
+                    let mut contents = Vec::new();
+                    file.read_to_end(&mut contents).map_err(extract_error)?;
+                    // Antivirus software may briefly lock freshly created files on Windows
+                    retry(Fixed::from_millis(500).take(5), || {
+                        let res = std::fs::write(&outpath, &contents);
+                        if let Err(e) = &res {
+                            debug!("Failed to write '{}': {}, retrying", outpath.display(), e);
                         }
+                        res
+                    })
+                    .map_err(|e| extract_error(e.error))?;
+                    #[cfg(unix)]
+                    if let Some(mode) = file.unix_mode() {
+                        std::fs::set_permissions(&outpath, std::fs::Permissions::from_mode(mode))
+                            .map_err(extract_error)?;
                     }
-                } else {
-                    zipfile.extract(output_directory).unwrap();
                 }
             }
             "gz" => {
                 debug!("Extracting tar.gz file to '{}'", output_directory);
 
-                let bytes = bytes.to_vec();
-                let tarfile = GzDecoder::new(bytes.as_slice());
+                let tarfile = GzDecoder::new(bytes);
                 let mut archive = Archive::new(tarfile);
-                archive.unpack(output_directory)?;
+                archive
+                    .unpack(get_extended_length_path(Path::new(output_directory))?)
+                    .map_err(|e| Error::ExtractFile(file_path.clone(), e.to_string()))?;
             }
             "xz" => {
                 debug!("Extracting tar.xz file to '{}'", output_directory);
-                let bytes = bytes.to_vec();
-                let tarfile = XzDecoder::new(bytes.as_slice());
+                let tarfile = XzDecoder::new(bytes);
                 let mut archive = Archive::new(tarfile);
-                archive.unpack(output_directory)?;
+                archive
+                    .unpack(get_extended_length_path(Path::new(output_directory))?)
+                    .map_err(|e| Error::ExtractFile(file_path.clone(), e.to_string()))?;
             }
             _ => {
                 return Err(Error::UnsuportedFileExtension(extension.to_string()));
@@ -124,34 +312,111 @@ pub async fn download_file(
     } else {
         debug!("Creating file: '{}'", file_path);
         let mut out = File::create(&file_path)?;
-        out.write_all(&bytes)?;
+        out.write_all(bytes)?;
     }
     Ok(file_path)
 }
 
+/// Gets a path that supports more than `MAX_PATH` characters on Windows, by using the `\\?\` prefix.
+///
+/// Paths are returned unmodified on other systems.
+fn get_extended_length_path(path: &Path) -> Result<PathBuf, Error> {
+    #[cfg(windows)]
+    {
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            env::current_dir()?.join(path)
+        };
+        let path = path.display().to_string().replace('/', "\\");
+        if path.starts_with(r"\\") {
+            Ok(PathBuf::from(path))
+        } else {
+            Ok(PathBuf::from(format!(r"\\?\{path}")))
+        }
+    }
+    #[cfg(not(windows))]
+    Ok(path.to_path_buf())
+}
+
+/// Gets the espup cache directory.
+pub fn get_cache_dir() -> PathBuf {
+    BaseDirs::new().unwrap().cache_dir().join("espup")
+}
+
 /// Installs or updates the Espressif Rust ecosystem.
 pub async fn install(args: InstallOpts, install_mode: InstallMode) -> Result<()> {
-    match install_mode {
-        InstallMode::Install => info!("Installing the Espressif Rust ecosystem"),
-        InstallMode::Update => info!("Updating the Espressif Rust ecosystem"),
+    let phase = match install_mode {
+        InstallMode::Install => {
+            info!("Installing the Espressif Rust ecosystem");
+            "install"
+        }
+        InstallMode::Update => {
+            info!("Updating the Espressif Rust ecosystem");
+            "update"
+        }
+    };
+    let start = Instant::now();
+    let shell = args.shell.unwrap_or_else(Shell::detect);
+    let export_file = get_export_file(args.export_file, shell)?;
+    if is_json_events() && is_stdout(&export_file) {
+        return Err(Error::ConflictingStdout.into());
     }
-    let export_file = get_export_file(args.export_file)?;
-    let mut exports: Vec<String> = Vec::new();
+    emit(Event::Started {
+        phase: phase.to_string(),
+    });
+    let template = match &args.export_template {
+        Some(template) => {
+            debug!("Reading export file template '{}'", template.display());
+            Some(read_to_string(template).map_err(Error::IoError)?)
+        }
+        None => None,
+    };
+    let mut exports: Vec<Export> = Vec::new();
     let host_triple = get_host_triple(args.default_host)?;
     let xtensa_rust_version = if let Some(toolchain_version) = &args.toolchain_version {
         if !args.skip_version_parse {
-            XtensaRust::parse_version(toolchain_version)?
+            XtensaRust::parse_version(toolchain_version).await?
         } else {
             toolchain_version.clone()
         }
     } else {
         XtensaRust::get_latest_version().await?
     };
-    let toolchain_dir = get_rustup_home().join("toolchains").join(args.name);
+    let gcc_version = Gcc::parse_version(&args.gcc_version)?;
+    let toolchain_dir = get_rustup_home().join("toolchains").join(&args.name);
+    if matches!(install_mode, InstallMode::Install)
+        && toolchain_dir.exists()
+        && !confirm(
+            &format!(
+                "The '{}' toolchain already exists in '{}', its components may be replaced. Continue?",
+                args.name,
+                toolchain_dir.display()
+            ),
+            args.yes,
+        )?
+    {
+        return Err(Error::Aborted.into());
+    }
+    let gcc_dir = match args.gcc_path {
+        Some(gcc_path) if gcc_path.is_relative() => {
+            env::current_dir().map_err(Error::IoError)?.join(gcc_path)
+        }
+        Some(gcc_path) => gcc_path,
+        None => toolchain_dir.clone(),
+    };
+    let mut receipt = Receipt::load(&args.name)?;
+    let llvm_version = if args.llvm_channel == "nightly" {
+        Some(Llvm::get_nightly_version().await?)
+    } else {
+        args.llvm_version
+    };
     let llvm: Llvm = Llvm::new(
         &toolchain_dir,
         &host_triple,
         args.extended_llvm,
+        args.legacy_llvm_path,
+        llvm_version.as_deref(),
         &xtensa_rust_version,
     )?;
     let targets = args.targets;
@@ -170,20 +435,38 @@ pub async fn install(args: InstallOpts, install_mode: InstallMode) -> Result<()>
 
     debug!(
         "Arguments:
+            - Build tools: {}
             - Export file: {:?}
+            - GCC path: {:?}
+            - GCC version: {}
+            - GDB: {}
+            - Jobs: {}
             - Host triple: {}
             - LLVM Toolchain: {:?}
             - Nightly version: {:?}
+            - probe-rs: {}
             - Rust Toolchain: {:?}
+            - Shell: {:?}
+            - Skip GCC: {}
+            - Skip LLVM: {}
             - Skip version parsing: {}
             - Targets: {:?}
             - Toolchain path: {:?}
             - Toolchain version: {:?}",
+        &args.build_tools,
         &export_file,
+        &gcc_dir,
+        gcc_version,
+        &args.gdb,
+        args.jobs,
         host_triple,
         &llvm,
         &args.nightly_version,
+        &args.probe_rs,
         xtensa_rust,
+        shell,
+        &args.skip_gcc,
+        &args.skip_llvm,
         &args.skip_version_parse,
         targets,
         &toolchain_dir,
@@ -200,68 +483,276 @@ pub async fn install(args: InstallOpts, install_mode: InstallMode) -> Result<()>
         to_install.push(Box::new(xtensa_rust.to_owned()));
     }
 
-    to_install.push(Box::new(llvm));
+    if !args.skip_llvm {
+        to_install.push(Box::new(llvm));
+    }
 
     if targets.iter().any(|t| t.is_riscv()) {
         let riscv_target = RiscVTarget::new(&args.nightly_version);
         to_install.push(Box::new(riscv_target));
     }
 
-    if !args.std {
-        if targets
-            .iter()
-            .any(|t| t == &Target::ESP32 || t == &Target::ESP32S2 || t == &Target::ESP32S3)
-        {
-            let xtensa_gcc = Gcc::new(XTENSA_GCC, &host_triple, &toolchain_dir);
+    let mut gcc_receipts = Vec::new();
+    if !args.std && !args.skip_gcc {
+        for arch in get_xtensa_gcc_arches(&gcc_version, &targets) {
+            let xtensa_gcc = Gcc::new(arch, &host_triple, &gcc_dir, &gcc_version);
+            gcc_receipts.push(xtensa_gcc.receipt());
             to_install.push(Box::new(xtensa_gcc));
         }
 
         // By default only install the Espressif RISC-V toolchain if the user explicitly wants to
         if args.esp_riscv_gcc && targets.iter().any(|t| t != &Target::ESP32) {
-            let riscv_gcc = Gcc::new(RISCV_GCC, &host_triple, &toolchain_dir);
+            let riscv_gcc = Gcc::new(RISCV_GCC, &host_triple, &gcc_dir, &gcc_version);
+            gcc_receipts.push(riscv_gcc.receipt());
             to_install.push(Box::new(riscv_gcc));
         }
     }
 
+    if args.build_tools {
+        to_install.push(Box::new(Cmake::new(&host_triple, &toolchain_dir)));
+        to_install.push(Box::new(Ninja::new(&host_triple, &toolchain_dir)));
+    }
+
+    if args.gdb {
+        for arch in get_gdb_arches(&targets) {
+            to_install.push(Box::new(Gdb::new(arch, &host_triple, &toolchain_dir)));
+        }
+    }
+
+    if args.probe_rs {
+        to_install.push(Box::new(ProbeRs::new(&host_triple, &toolchain_dir)));
+    }
+
     // With a list of applications to install, install them all in parallel.
+    let installation_start = Instant::now();
     let installable_items = to_install.len();
-    let (tx, mut rx) = mpsc::channel::<Result<Vec<String>, Error>>(installable_items);
+    let (tx, mut rx) =
+        mpsc::channel::<(String, Timings, Result<Vec<Export>, Error>)>(installable_items);
+    // Bound the number of concurrent downloads and extractions
+    let jobs = Arc::new(Semaphore::new(args.jobs.get()));
+    // Directories created by the installation, removed if it is interrupted
+    let new_toolchain = !toolchain_dir.exists();
+    let mut partial_paths: HashMap<String, PathBuf> = HashMap::new();
+    let mut tasks = Vec::new();
+    let mut components: Vec<(String, String, Option<PathBuf>)> = Vec::new();
     for app in to_install {
+        if let Some(path) = app.path().filter(|path| !path.exists()) {
+            partial_paths.insert(app.name(), path);
+        }
+        components.push((app.name(), app.version(), app.path()));
         let tx = tx.clone();
+        let jobs = jobs.clone();
         let retry_strategy = FixedInterval::from_millis(50).take(3);
-        tokio::spawn(async move {
-            let res = Retry::spawn(retry_strategy, || async {
-                let res = app.install().await;
-                if res.is_err() {
-                    warn!("Installation for '{}' failed, retrying", app.name());
+        let span = info_span!("component", name = %app.name());
+        tasks.push(tokio::spawn(
+            async move {
+                let _permit = jobs.acquire().await.unwrap();
+                debug!("Starting '{}' installation", app.name());
+                emit(Event::Started { phase: app.name() });
+                let start = Instant::now();
+                let (res, mut timings) = record(Retry::spawn(retry_strategy, || async {
+                    let res = app.install().await;
+                    if res.is_err() {
+                        warn!("Installation for '{}' failed, retrying", app.name());
+                    }
+                    res
+                }))
+                .await;
+                let elapsed = start.elapsed();
+                timings.total = elapsed;
+                if res.is_ok() {
+                    info!(
+                        "Finished '{}' installation in {}",
+                        app.name(),
+                        format_duration(elapsed)
+                    );
+                    emit(Event::Finished {
+                        phase: app.name(),
+                        duration_secs: elapsed.as_secs_f64(),
+                    });
                 }
-                res
-            })
-            .await;
-            tx.send(res).await.unwrap();
-        });
+                tx.send((app.name(), timings, res)).await.unwrap();
+            }
+            .instrument(span),
+        ));
     }
 
     // Read the results of the install tasks as they complete.
-    for _ in 0..installable_items {
-        let names = rx.recv().await.unwrap()?;
-        exports.extend(names);
+    let mut timings: Vec<(String, Timings)> = Vec::new();
+    let mut results: HashMap<String, Vec<Export>> = HashMap::new();
+    let interrupt = tokio::signal::ctrl_c();
+    tokio::pin!(interrupt);
+    for done in 1..=installable_items {
+        let (name, timing, res) = tokio::select! {
+            message = rx.recv() => message.unwrap(),
+            _ = &mut interrupt => {
+                warn!("Installation interrupted, removing the partially installed components");
+                for task in &tasks {
+                    task.abort();
+                }
+                // Aborting only takes effect at the next await point, wait for the running
+                // extractions to stop before removing their directories
+                for task in tasks {
+                    let _ = task.await;
+                }
+                let mut removed: Vec<&PathBuf> = partial_paths.values().collect();
+                if new_toolchain {
+                    removed.retain(|path| !path.starts_with(&toolchain_dir));
+                    removed.push(&toolchain_dir);
+                }
+                for path in removed {
+                    if let Err(e) = remove_dir(path).await {
+                        warn!("{}", e);
+                    }
+                }
+                return Err(Error::Interrupted.into());
+            }
+        };
+        partial_paths.remove(&name);
+        results.insert(name.clone(), res?);
+        timings.push((name, timing));
+        emit(Event::Progress {
+            percent: (done * 100 / installable_items) as u8,
+        });
+    }
+    // Installations finish in any order, collect the exports in installation order so the `PATH`
+    // precedence does not depend on it
+    for (name, _, _) in &components {
+        exports.extend(results.remove(name).unwrap_or_default());
+    }
+
+    for gcc in gcc_receipts {
+        receipt.add_gcc(gcc);
+    }
+    receipt.exports = exports.clone();
+    if let Some(xtensa_rust) = &xtensa_rust {
+        receipt.xtensa_rust = Some(xtensa_rust.version.clone());
+    }
+    receipt.save(&args.name)?;
+
+    info!("Installation timings:");
+    if args.timings {
+        for line in format_timings_table(&timings) {
+            info!("  {}", line);
+        }
+    } else {
+        for (name, timing) in &timings {
+            info!("  - {}: {}", name, format_duration(timing.total));
+        }
     }
+    info!(
+        "  - Total: {}",
+        format_duration(installation_start.elapsed())
+    );
 
-    create_export_file(&export_file, &exports)?;
+    warn_shadowed_tools(&exports);
+    let mut lines = if args.relocatable {
+        format_relocatable_exports(&exports, shell, &toolchain_dir)
+    } else {
+        format_exports(&exports, shell)
+    };
+    if let Some(template) = &template {
+        lines = render_template(template, &lines, &exports)?;
+    }
+    create_export_file(&export_file, &lines, shell)?;
+    if !is_stdout(&export_file) {
+        receipt.add_file(export_file.clone());
+    }
+    if let Some(dotenv_file) = args.dotenv {
+        let dotenv_file = env::current_dir()
+            .map_err(Error::IoError)?
+            .join(dotenv_file);
+        create_dotenv_file(&dotenv_file, &exports)?;
+        receipt.add_dotenv_file(dotenv_file);
+    }
+    if args.github_actions || is_github_actions() {
+        update_github_env(&exports)?;
+    }
+    if args.envrc {
+        let envrc = env::current_dir().map_err(Error::IoError)?.join(".envrc");
+        update_envrc(&envrc, &exports)?;
+        receipt.add_file(envrc);
+    }
+    if args.profile_install {
+        if is_stdout(&export_file) {
+            warn!("Exports are printed to stdout, skipping the shell profile modification");
+        } else if let Some(profile) = shell.get_profile() {
+            install_profile(&profile, &export_file, shell)?;
+            receipt.add_profile(profile);
+        } else {
+            warn!(
+                "The {:?} shell has no profile, skipping the shell profile modification",
+                shell
+            );
+        }
+    }
+    receipt.save(&args.name)?;
     #[cfg(windows)]
-    set_env()?;
+    set_env(&exports)?;
+
     match install_mode {
         InstallMode::Install => info!("Installation successfully completed!"),
         InstallMode::Update => info!("Update successfully completed!"),
     }
 
-    print_post_install_msg(&export_file)?;
+    emit(Event::Finished {
+        phase: phase.to_string(),
+        duration_secs: start.elapsed().as_secs_f64(),
+    });
+    if !is_json_events() && !is_stdout(&export_file) {
+        println!();
+        for line in format_summary_table(&components) {
+            println!("\t{line}");
+        }
+        print_post_install_msg(&export_file, shell)?;
+    }
     Ok(())
 }
 
+/// Formats a table with the version and location of each installed component.
+pub fn format_summary_table(components: &[(String, String, Option<PathBuf>)]) -> Vec<String> {
+    let name_width = components
+        .iter()
+        .map(|(name, _, _)| name.len())
+        .chain(["Component".len()])
+        .max()
+        .unwrap();
+    let version_width = components
+        .iter()
+        .map(|(_, version, _)| version.len())
+        .chain(["Version".len()])
+        .max()
+        .unwrap();
+    let mut lines = vec![format!(
+        "{:<name_width$}  {:<version_width$}  Location",
+        "Component", "Version"
+    )];
+    for (name, version, path) in components {
+        let location = path
+            .as_ref()
+            .map_or("managed by rustup".to_string(), |path| {
+                path.display().to_string()
+            });
+        lines.push(format!(
+            "{:<name_width$}  {:<version_width$}  {}",
+            name, version, location
+        ));
+    }
+    lines
+}
+
 /// Queries the GitHub API and returns the JSON response.
-pub fn github_query(url: &str) -> Result<serde_json::Value, Error> {
+pub async fn github_query(url: &str) -> Result<serde_json::Value, Error> {
+    // The blocking GitHub client can not be dropped within the async runtime
+    let url = url.to_string();
+    let span = Span::current();
+    tokio::task::spawn_blocking(move || span.in_scope(|| github_query_blocking(&url)))
+        .await
+        .unwrap()
+}
+
+#[instrument(name = "github", skip_all)]
+fn github_query_blocking(url: &str) -> Result<serde_json::Value, Error> {
     debug!("Querying GitHub API: '{}'", url);
     let mut headers = header::HeaderMap::new();
     headers.insert(header::USER_AGENT, "espup".parse().unwrap());
@@ -295,7 +786,7 @@ pub fn github_query(url: &str) -> Result<serde_json::Value, Error> {
             Ok(json)
         },
     )
-    .unwrap();
+    .map_err(|e| e.error)?;
     Ok(json)
 }
 
@@ -312,3 +803,101 @@ pub async fn remove_dir(path: &Path) -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::toolchain::{format_summary_table, parse_checksum, verify_checksum, write_file};
+    use std::{
+        fs::read_to_string,
+        io::{Cursor, Write},
+        path::PathBuf,
+    };
+    use tempfile::TempDir;
+    use zip::{write::FileOptions, ZipWriter};
+
+    #[test]
+    fn test_parse_checksum() {
+        let checksums = "\
+            0123abcd  llvm-esp-16.0.4-20231113-linux-amd64.tar.xz\n\
+            4567EFAB *libs_llvm-esp-16.0.4-20231113-linux-amd64.tar.xz\n";
+        assert_eq!(
+            parse_checksum(checksums, "llvm-esp-16.0.4-20231113-linux-amd64.tar.xz"),
+            Some("0123abcd".to_string())
+        );
+        assert_eq!(
+            parse_checksum(
+                checksums,
+                "libs_llvm-esp-16.0.4-20231113-linux-amd64.tar.xz"
+            ),
+            Some("4567efab".to_string())
+        );
+        assert_eq!(
+            parse_checksum(checksums, "llvm-esp-16.0.4-20231113-win64.zip"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_write_file_zip() {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.add_directory("esp/dir with spaces/", FileOptions::default())
+            .unwrap();
+        zip.start_file("esp/dir with spaces/file.txt", FileOptions::default())
+            .unwrap();
+        zip.write_all(b"content").unwrap();
+        zip.start_file("other.txt", FileOptions::default()).unwrap();
+        zip.write_all(b"other").unwrap();
+        let bytes = zip.finish().unwrap().into_inner();
+
+        // Extracts every entry
+        let temp_dir = TempDir::new().unwrap();
+        let output_directory = temp_dir.path().to_str().unwrap();
+        write_file(&bytes, "file.zip", output_directory, true, false).unwrap();
+        assert_eq!(
+            read_to_string(temp_dir.path().join("esp/dir with spaces/file.txt")).unwrap(),
+            "content"
+        );
+        assert!(temp_dir.path().join("other.txt").exists());
+
+        // Strips the `esp/` prefix and skips the rest of entries
+        let temp_dir = TempDir::new().unwrap();
+        let output_directory = temp_dir.path().to_str().unwrap();
+        write_file(&bytes, "file.zip", output_directory, true, true).unwrap();
+        assert_eq!(
+            read_to_string(temp_dir.path().join("dir with spaces/file.txt")).unwrap(),
+            "content"
+        );
+        assert!(!temp_dir.path().join("other.txt").exists());
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let sha = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert!(verify_checksum(b"hello", sha, "hello.txt").is_ok());
+        assert!(verify_checksum(b"hello!", sha, "hello.txt").is_err());
+    }
+
+    #[test]
+    fn test_format_summary_table() {
+        let components = vec![
+            (
+                "LLVM".to_string(),
+                "esp-17.0.1_20240419".to_string(),
+                Some(PathBuf::from("/esp/esp-clang")),
+            ),
+            (
+                "RISC-V Rust target".to_string(),
+                "nightly".to_string(),
+                None,
+            ),
+        ];
+        assert_eq!(
+            format_summary_table(&components),
+            vec![
+                "Component           Version              Location",
+                "LLVM                esp-17.0.1_20240419  /esp/esp-clang",
+                "RISC-V Rust target  nightly              managed by rustup",
+            ]
+        );
+    }
+}
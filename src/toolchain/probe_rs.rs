@@ -0,0 +1,150 @@
+//! probe-rs source and installation tools.
+
+use crate::{
+    env::Export,
+    error::Error,
+    host_triple::HostTriple,
+    toolchain::{download_file, get_checksum, Installable},
+};
+use async_trait::async_trait;
+use miette::Result;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+const PROBE_RS_REPOSITORY: &str = "https://github.com/probe-rs/probe-rs/releases/download";
+pub const PROBE_RS_VERSION: &str = "0.24.0";
+const PROBE_RS_UDEV_RULES: &str = "https://probe.rs/files/69-probe-rs.rules";
+const UDEV_RULES_FILE: &str = "69-probe-rs.rules";
+
+#[derive(Debug, Clone)]
+pub struct ProbeRs {
+    /// Host triple.
+    pub host_triple: HostTriple,
+    /// probe-rs path.
+    pub path: PathBuf,
+}
+
+impl ProbeRs {
+    /// Gets the name of the release artifact, without extension.
+    fn get_artifact_name(&self) -> String {
+        // Only MSVC binaries are released for Windows
+        let host_triple = match self.host_triple {
+            HostTriple::X86_64PcWindowsGnu => HostTriple::X86_64PcWindowsMsvc,
+            _ => self.host_triple.clone(),
+        };
+        format!("probe-rs-tools-{host_triple}")
+    }
+
+    /// Gets the binary path.
+    pub fn get_bin_path(&self) -> String {
+        let bin_path = format!(
+            "{}/{}",
+            self.path.to_str().unwrap(),
+            self.get_artifact_name()
+        );
+        match std::cfg!(windows) {
+            true => bin_path.replace('/', "\\"),
+            false => bin_path,
+        }
+    }
+
+    /// Create a new instance with default values and proper toolchain name.
+    pub fn new(host_triple: &HostTriple, toolchain_path: &Path) -> Self {
+        Self {
+            host_triple: host_triple.clone(),
+            path: toolchain_path.join("probe-rs").join(PROBE_RS_VERSION),
+        }
+    }
+
+    /// Downloads the probe-rs udev rules, which need to be installed by the user with root permissions.
+    async fn download_udev_rules(&self) -> Result<(), Error> {
+        download_file(
+            PROBE_RS_UDEV_RULES.to_string(),
+            UDEV_RULES_FILE,
+            &self.path.display().to_string(),
+            false,
+            false,
+            None,
+        )
+        .await?;
+        info!(
+            "To access debug probes without root permissions, install the udev rules by running: 'sudo cp {} /etc/udev/rules.d/ && sudo udevadm control --reload && sudo udevadm trigger'",
+            self.path.join(UDEV_RULES_FILE).display()
+        );
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Installable for ProbeRs {
+    async fn install(&self) -> Result<Vec<Export>, Error> {
+        info!("Installing probe-rs");
+        debug!("probe-rs path: {}", self.path.display());
+
+        if self.path.exists() {
+            warn!(
+                "Previous installation of probe-rs exists in: '{}'. Reusing this installation",
+                &self.path.display()
+            );
+        } else {
+            let extension = match self.host_triple {
+                HostTriple::X86_64PcWindowsMsvc | HostTriple::X86_64PcWindowsGnu => "zip",
+                _ => "tar.xz",
+            };
+            let probe_rs_file = format!("{}.{}", self.get_artifact_name(), extension);
+            let probe_rs_dist_url =
+                format!("{PROBE_RS_REPOSITORY}/v{PROBE_RS_VERSION}/{probe_rs_file}");
+            let checksum =
+                get_checksum(&format!("{probe_rs_dist_url}.sha256"), &probe_rs_file).await;
+            download_file(
+                probe_rs_dist_url,
+                &format!("probe-rs.{extension}"),
+                &self.path.display().to_string(),
+                true,
+                false,
+                checksum,
+            )
+            .await?;
+
+            if matches!(
+                self.host_triple,
+                HostTriple::X86_64UnknownLinuxGnu | HostTriple::Aarch64UnknownLinuxGnu
+            ) {
+                self.download_udev_rules().await?;
+            }
+        }
+
+        Ok(vec![Export::Path(self.get_bin_path())])
+    }
+
+    fn name(&self) -> String {
+        "probe-rs".to_string()
+    }
+
+    fn path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+
+    fn version(&self) -> String {
+        PROBE_RS_VERSION.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{host_triple::HostTriple, toolchain::probe_rs::ProbeRs};
+    use std::path::Path;
+
+    #[test]
+    fn test_probe_rs_artifact_name() {
+        let toolchain_path = Path::new("/esp");
+        assert_eq!(
+            ProbeRs::new(&HostTriple::Aarch64AppleDarwin, toolchain_path).get_artifact_name(),
+            "probe-rs-tools-aarch64-apple-darwin"
+        );
+        assert_eq!(
+            ProbeRs::new(&HostTriple::X86_64PcWindowsGnu, toolchain_path).get_artifact_name(),
+            "probe-rs-tools-x86_64-pc-windows-msvc"
+        );
+    }
+}
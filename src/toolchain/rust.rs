@@ -1,11 +1,12 @@
 //! Xtensa Rust Toolchain source and installation tools.
 
 use crate::{
+    env::Export,
     error::Error,
     host_triple::HostTriple,
     toolchain::{
         download_file,
-        gcc::{RISCV_GCC, XTENSA_GCC},
+        gcc::{LEGACY_XTENSA_GCC, RISCV_GCC, XTENSA_GCC},
         github_query,
         llvm::CLANG_NAME,
         Installable,
@@ -13,7 +14,6 @@ use crate::{
 };
 use async_trait::async_trait;
 use directories::BaseDirs;
-use log::{debug, info, warn};
 use miette::Result;
 use regex::Regex;
 #[cfg(unix)]
@@ -29,6 +29,7 @@ use std::{
 #[cfg(unix)]
 use tempfile::tempdir_in;
 use tokio::fs::{remove_dir_all, remove_file};
+use tracing::{debug, info, warn};
 
 /// Xtensa Rust Toolchain repository
 const DEFAULT_XTENSA_RUST_REPOSITORY: &str =
@@ -72,11 +73,11 @@ pub struct XtensaRust {
 impl XtensaRust {
     /// Get the latest version of Xtensa Rust toolchain.
     pub async fn get_latest_version() -> Result<String> {
-        let json = github_query(XTENSA_RUST_LATEST_API_URL)?;
+        let json = github_query(XTENSA_RUST_LATEST_API_URL).await?;
         let mut version = json["tag_name"].to_string();
 
         version.retain(|c| c != 'v' && c != '"');
-        Self::parse_version(&version)?;
+        Self::parse_version(&version).await?;
         debug!("Latest Xtensa Rust version: {}", version);
         Ok(version)
     }
@@ -115,11 +116,11 @@ impl XtensaRust {
     }
 
     /// Parses the version of the Xtensa toolchain.
-    pub fn parse_version(arg: &str) -> Result<String, Error> {
+    pub async fn parse_version(arg: &str) -> Result<String, Error> {
         debug!("Parsing Xtensa Rust version: {}", arg);
         let re_extended = Regex::new(RE_EXTENDED_SEMANTIC_VERSION).unwrap();
         let re_semver = Regex::new(RE_SEMANTIC_VERSION).unwrap();
-        let json = github_query(XTENSA_RUST_API_URL)?;
+        let json = github_query(XTENSA_RUST_API_URL).await?;
         if re_semver.is_match(arg) {
             let mut extended_versions: Vec<String> = Vec::new();
             for release in json.as_array().unwrap() {
@@ -168,6 +169,9 @@ impl XtensaRust {
             if !entry_name.contains(RISCV_GCC)
                 && !entry_name.contains(XTENSA_GCC)
                 && !entry_name.contains(CLANG_NAME)
+                && !LEGACY_XTENSA_GCC
+                    .iter()
+                    .any(|gcc| entry_path.ends_with(gcc))
             {
                 if entry_path.is_dir() {
                     remove_dir_all(Path::new(&entry_name))
@@ -184,7 +188,7 @@ impl XtensaRust {
 
 #[async_trait]
 impl Installable for XtensaRust {
-    async fn install(&self) -> Result<Vec<String>, Error> {
+    async fn install(&self) -> Result<Vec<Export>, Error> {
         if self.toolchain_destination.exists() {
             let toolchain_name = format!(
                 "+{}",
@@ -233,6 +237,7 @@ impl Installable for XtensaRust {
                 tmp_dir_path,
                 true,
                 false,
+                None,
             )
             .await?;
 
@@ -266,6 +271,7 @@ impl Installable for XtensaRust {
                 tmp_dir_path,
                 true,
                 false,
+                None,
             )
             .await?;
             info!("Installing 'rust-src' component for Xtensa Rust toolchain");
@@ -297,6 +303,7 @@ impl Installable for XtensaRust {
                 &self.toolchain_destination.display().to_string(),
                 true,
                 true,
+                None,
             )
             .await?;
         }
@@ -307,6 +314,14 @@ impl Installable for XtensaRust {
     fn name(&self) -> String {
         "Xtensa Rust".to_string()
     }
+
+    fn path(&self) -> Option<PathBuf> {
+        Some(self.toolchain_destination.clone())
+    }
+
+    fn version(&self) -> String {
+        self.version.clone()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -348,7 +363,7 @@ impl RiscVTarget {
 
 #[async_trait]
 impl Installable for RiscVTarget {
-    async fn install(&self) -> Result<Vec<String>, Error> {
+    async fn install(&self) -> Result<Vec<Export>, Error> {
         info!(
             "Installing RISC-V Rust targets ('riscv32imc-unknown-none-elf' and 'riscv32imac-unknown-none-elf') for '{}' toolchain",            &self.nightly_version
         );
@@ -380,6 +395,10 @@ impl Installable for RiscVTarget {
     fn name(&self) -> String {
         "RISC-V Rust target".to_string()
     }
+
+    fn version(&self) -> String {
+        self.nightly_version.clone()
+    }
 }
 
 /// Gets the artifact extension based on the host architecture.
@@ -443,21 +462,39 @@ mod tests {
     use std::env;
     use tempfile::TempDir;
 
-    #[test]
-    fn test_xtensa_rust_parse_version() {
+    #[tokio::test]
+    async fn test_xtensa_rust_parse_version() {
         initialize_logger("debug");
-        assert_eq!(XtensaRust::parse_version("1.65.0.0").unwrap(), "1.65.0.0");
-        assert_eq!(XtensaRust::parse_version("1.65.0.1").unwrap(), "1.65.0.1");
-        assert_eq!(XtensaRust::parse_version("1.64.0.0").unwrap(), "1.64.0.0");
-        assert_eq!(XtensaRust::parse_version("1.63.0").unwrap(), "1.63.0.2");
-        assert_eq!(XtensaRust::parse_version("1.65.0").unwrap(), "1.65.0.1");
-        assert_eq!(XtensaRust::parse_version("1.64.0").unwrap(), "1.64.0.0");
-        assert!(XtensaRust::parse_version("422.0.0").is_err());
-        assert!(XtensaRust::parse_version("422.0.0.0").is_err());
-        assert!(XtensaRust::parse_version("a.1.1.1").is_err());
-        assert!(XtensaRust::parse_version("1.1.1.1.1").is_err());
-        assert!(XtensaRust::parse_version("1..1.1").is_err());
-        assert!(XtensaRust::parse_version("1._.*.1").is_err());
+        assert_eq!(
+            XtensaRust::parse_version("1.65.0.0").await.unwrap(),
+            "1.65.0.0"
+        );
+        assert_eq!(
+            XtensaRust::parse_version("1.65.0.1").await.unwrap(),
+            "1.65.0.1"
+        );
+        assert_eq!(
+            XtensaRust::parse_version("1.64.0.0").await.unwrap(),
+            "1.64.0.0"
+        );
+        assert_eq!(
+            XtensaRust::parse_version("1.63.0").await.unwrap(),
+            "1.63.0.2"
+        );
+        assert_eq!(
+            XtensaRust::parse_version("1.65.0").await.unwrap(),
+            "1.65.0.1"
+        );
+        assert_eq!(
+            XtensaRust::parse_version("1.64.0").await.unwrap(),
+            "1.64.0.0"
+        );
+        assert!(XtensaRust::parse_version("422.0.0").await.is_err());
+        assert!(XtensaRust::parse_version("422.0.0.0").await.is_err());
+        assert!(XtensaRust::parse_version("a.1.1.1").await.is_err());
+        assert!(XtensaRust::parse_version("1.1.1.1.1").await.is_err());
+        assert!(XtensaRust::parse_version("1..1.1").await.is_err());
+        assert!(XtensaRust::parse_version("1._.*.1").await.is_err());
     }
 
     #[test]
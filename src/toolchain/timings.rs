@@ -0,0 +1,110 @@
+//! Durations of the installation phases of each component.
+
+use std::{cell::RefCell, future::Future, time::Duration};
+
+tokio::task_local! {
+    static TIMINGS: RefCell<Timings>;
+}
+
+/// Durations of the installation phases of a component.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Timings {
+    /// Time spent downloading files, or reading them from the cache.
+    pub download: Duration,
+    /// Time spent uncompressing and writing files.
+    pub extraction: Duration,
+    /// Total time of the installation.
+    pub total: Duration,
+}
+
+impl Timings {
+    /// Time not spent downloading or extracting files, e.g. running installers.
+    pub fn configuration(&self) -> Duration {
+        self.total.saturating_sub(self.download + self.extraction)
+    }
+}
+
+/// Runs the installation of a component, recording the durations of its phases.
+pub async fn record<F: Future>(future: F) -> (F::Output, Timings) {
+    TIMINGS
+        .scope(RefCell::new(Timings::default()), async {
+            let output = future.await;
+            (output, TIMINGS.with(|timings| *timings.borrow()))
+        })
+        .await
+}
+
+/// Adds time spent downloading files to the component being installed.
+pub fn add_download(elapsed: Duration) {
+    let _ = TIMINGS.try_with(|timings| timings.borrow_mut().download += elapsed);
+}
+
+/// Adds time spent extracting files to the component being installed.
+pub fn add_extraction(elapsed: Duration) {
+    let _ = TIMINGS.try_with(|timings| timings.borrow_mut().extraction += elapsed);
+}
+
+/// Formats a duration as seconds with two decimals.
+pub fn format_duration(duration: Duration) -> String {
+    format!("{:.2}s", duration.as_secs_f64())
+}
+
+/// Formats a table with the durations of the phases of each component.
+pub fn format_timings_table(timings: &[(String, Timings)]) -> Vec<String> {
+    let width = timings
+        .iter()
+        .map(|(name, _)| name.len())
+        .chain(["Component".len()])
+        .max()
+        .unwrap();
+    let mut lines = vec![format!(
+        "{:<width$}  {:>10}  {:>10}  {:>13}  {:>10}",
+        "Component", "Download", "Extraction", "Configuration", "Total"
+    )];
+    for (name, timing) in timings {
+        lines.push(format!(
+            "{:<width$}  {:>10}  {:>10}  {:>13}  {:>10}",
+            name,
+            format_duration(timing.download),
+            format_duration(timing.extraction),
+            format_duration(timing.configuration()),
+            format_duration(timing.total)
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::toolchain::timings::{add_download, format_timings_table, record, Timings};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_record() {
+        let ((), timings) = record(async {
+            add_download(Duration::from_millis(10));
+            add_download(Duration::from_millis(5));
+        })
+        .await;
+        assert_eq!(timings.download, Duration::from_millis(15));
+        assert_eq!(timings.extraction, Duration::ZERO);
+        // Outside of a recording, durations are ignored
+        add_download(Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_format_timings_table() {
+        let timings = Timings {
+            download: Duration::from_millis(1500),
+            extraction: Duration::from_millis(250),
+            total: Duration::from_secs(2),
+        };
+        assert_eq!(
+            format_timings_table(&[("llvm".to_string(), timings)]),
+            vec![
+                "Component    Download  Extraction  Configuration       Total",
+                "llvm            1.50s       0.25s          0.25s       2.00s",
+            ]
+        );
+    }
+}
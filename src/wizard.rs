@@ -0,0 +1,113 @@
+//! Interactive installation wizard.
+
+use crate::{
+    cli::InstallOpts,
+    env::{get_export_file, Shell},
+    error::Error,
+    interactive::prompt_error,
+    targets::Target,
+    toolchain::rust::get_rustup_home,
+};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Select};
+use miette::Result;
+use std::path::{Path, PathBuf};
+use strum::IntoEnumIterator;
+use tracing::{info, warn};
+
+/// Formats the summary of the installation.
+pub fn format_summary(args: &InstallOpts, toolchain_dir: &Path, export_file: &Path) -> Vec<String> {
+    let mut targets: Vec<String> = args.targets.iter().map(Target::to_string).collect();
+    targets.sort();
+    vec![
+        format!("Targets: {}", targets.join(", ")),
+        format!("Applications: {}", if args.std { "std" } else { "no_std" }),
+        format!("Toolchain: {} ({})", args.name, toolchain_dir.display()),
+        format!("Export file: {}", export_file.display()),
+    ]
+}
+
+/// Prompts for the installation options, returns `None` if the installation is cancelled.
+pub fn run_wizard(mut args: InstallOpts) -> Result<Option<InstallOpts>, Error> {
+    let theme = ColorfulTheme::default();
+
+    let targets: Vec<Target> = Target::iter().collect();
+    let defaults: Vec<bool> = targets.iter().map(|t| args.targets.contains(t)).collect();
+    loop {
+        let selection = MultiSelect::with_theme(&theme)
+            .with_prompt("Select the chips to develop for")
+            .items(&targets)
+            .defaults(&defaults)
+            .interact()
+            .map_err(prompt_error)?;
+        if !selection.is_empty() {
+            args.targets = selection.into_iter().map(|i| targets[i]).collect();
+            break;
+        }
+        warn!("At least one chip needs to be selected");
+    }
+
+    args.std = Select::with_theme(&theme)
+        .with_prompt("Select the kind of applications")
+        .items(&["no_std (bare metal)", "std (ESP-IDF)"])
+        .default(usize::from(args.std))
+        .interact()
+        .map_err(prompt_error)?
+        == 1;
+
+    args.name = Input::with_theme(&theme)
+        .with_prompt("Name of the Xtensa Rust toolchain")
+        .default(args.name)
+        .interact_text()
+        .map_err(prompt_error)?;
+    let toolchain_dir = get_rustup_home().join("toolchains").join(&args.name);
+
+    let shell = args.shell.unwrap_or_else(Shell::detect);
+    let export_file: String = Input::with_theme(&theme)
+        .with_prompt("Export file")
+        .default(
+            get_export_file(args.export_file, shell)?
+                .display()
+                .to_string(),
+        )
+        .interact_text()
+        .map_err(prompt_error)?;
+    let export_file = get_export_file(Some(PathBuf::from(export_file)), shell)?;
+    args.export_file = Some(export_file.clone());
+
+    for line in format_summary(&args, &toolchain_dir, &export_file) {
+        info!("{line}");
+    }
+    let proceed = Confirm::with_theme(&theme)
+        .with_prompt("Proceed with the installation?")
+        .default(true)
+        .interact()
+        .map_err(prompt_error)?;
+
+    Ok(proceed.then_some(args))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cli::InstallOpts, targets::Target, wizard::format_summary};
+    use clap::Parser;
+    use std::path::Path;
+
+    #[test]
+    fn test_format_summary() {
+        let mut args = InstallOpts::parse_from(["install", "--std"]);
+        args.targets = [Target::ESP32S3, Target::ESP32C3].into_iter().collect();
+        assert_eq!(
+            format_summary(
+                &args,
+                Path::new("/rustup/toolchains/esp"),
+                Path::new("/home/export-esp.sh")
+            ),
+            vec![
+                "Targets: esp32c3, esp32s3",
+                "Applications: std",
+                "Toolchain: esp (/rustup/toolchains/esp)",
+                "Export file: /home/export-esp.sh",
+            ]
+        );
+    }
+}
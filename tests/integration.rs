@@ -15,6 +15,24 @@ fn verify_help() {
         .success();
 }
 
+#[test]
+fn verify_containerize_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["containerize", "--help"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn verify_init_project_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["init-project", "--help"])
+        .assert()
+        .success();
+}
+
 #[test]
 fn verify_install_help() {
     assert_cmd::Command::cargo_bin("espup")
@@ -41,3 +59,12 @@ fn verify_uninstall_help() {
         .assert()
         .success();
 }
+
+#[test]
+fn verify_env_help() {
+    assert_cmd::Command::cargo_bin("espup")
+        .unwrap()
+        .args(["env", "--help"])
+        .assert()
+        .success();
+}